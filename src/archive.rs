@@ -24,500 +24,5664 @@ SOFTWARE.
 //! Module for [`TarArchiveRef`]. If the `alloc`-feature is enabled, this crate
 //! also exports `TarArchive`, which owns data on the heap.
 
-use crate::header::PosixHeader;
-use crate::tar_format_types::TarFormatString;
-use crate::{BLOCKSIZE, POSIX_1003_MAX_FILENAME_LEN};
+#[cfg(feature = "alloc")]
+use crate::header::ModeError;
+use crate::header::{
+    ArchiveFormat, ChecksumError, ModeFlags, PosixHeader, UnixTimestamp, VersionKind,
+};
+use crate::pax::PaxHeader;
+use crate::tar_format_types::{CapacityError, TarFormatOctal, TarFormatString};
+use crate::{BlockSource, TypeFlag, TypeFlagRaw, TypeFlagSet};
+#[cfg(feature = "alloc")]
+use crate::{BuildError, EntryOptions, TarBuilder, TarFormat};
+use crate::{BLOCKSIZE, NAME_LEN, POSIX_1003_MAX_FILENAME_LEN};
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
 #[cfg(feature = "alloc")]
 use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use core::fmt::{Debug, Display, Formatter};
+use core::num::ParseIntError;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use core::ops::Deref;
+#[cfg(feature = "alloc")]
+use core::ops::Range;
 use core::str::Utf8Error;
-use log::{error, warn};
+use log::warn;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
 
 /// Minimum amount of blocks that an archive must have to be considered sane.
 /// - one header block
 /// - two terminating zero blocks
 pub const MIN_BLOCK_COUNT: usize = 3;
 
+/// Number of blocks at the beginning of the data that
+/// [`TarArchiveRef::new_skip_junk`] scans for the first header.
+pub const MAX_JUNK_BLOCKS: usize = 4;
+
+/// Maximum number of symlinks and hardlinks that [`TarArchive::read_file`]
+/// follows before giving up. This protects against link cycles.
+#[cfg(feature = "alloc")]
+pub const MAX_LINK_DEPTH: usize = 32;
+
+/// Number of 512-byte blocks that a payload of `size` bytes occupies.
+#[must_use]
+pub const fn blocks_for_payload(size: u64) -> u64 {
+    size.div_ceil(BLOCKSIZE as u64)
+}
+
+/// Number of zero bytes that pad a payload of `size` bytes to the next
+/// 512-byte block boundary.
+#[must_use]
+pub const fn block_padding(size: u64) -> usize {
+    (BLOCKSIZE - (size % BLOCKSIZE as u64) as usize) % BLOCKSIZE
+}
+
+/// Looks up a fixed set of files in a [`TarArchiveRef`] in a single pass.
+///
+/// The data is returned as fields of a struct of type `Option<&[u8]>`. This
+/// is handy for loaders that expect specific files. A field is `None`, if the
+/// archive doesn't contain the file. See [`TarArchiveRef::read_many`].
+///
+/// ```rust
+/// use tar_no_std::{extract_named, TarArchiveRef};
+///
+/// let archive = include_bytes!("../tests/gnu_tar_default.tar");
+/// let archive = TarArchiveRef::new(archive).unwrap();
+/// let files = extract_named!(archive, {
+///     hello: "hello_world.txt",
+///     cmdline: "cmdline",
+/// });
+/// assert_eq!(files.hello, Some(b"Hello World\n".as_slice()));
+/// assert_eq!(files.cmdline, None);
+/// ```
+#[macro_export]
+macro_rules! extract_named {
+    ($archive:expr, { $($field:ident: $name:expr),* $(,)? }) => {{
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        struct ExtractedFiles<'a> {
+            $($field: Option<&'a [u8]>,)*
+        }
+        let [$($field),*] = $crate::TarArchiveRef::read_many(&$archive, [$($name),*]);
+        ExtractedFiles { $($field),* }
+    }};
+}
+
 /// Describes an entry in an archive.
 /// Currently only supports files but no directories.
 pub struct ArchiveEntry<'a> {
     filename: TarFormatString<POSIX_1003_MAX_FILENAME_LEN>,
     data: &'a [u8],
     size: usize,
+    posix_header: &'a PosixHeader,
+    header_block_index: BlockIndex,
+    /// The raw blocks including the extension records.
+    raw_blocks: &'a [u8],
+    /// Length of the extension records at the beginning of `raw_blocks`.
+    extension_len: usize,
+    pax: Option<PaxHeader<'a>>,
+    long_name: Option<&'a [u8]>,
+    long_link_target: Option<&'a [u8]>,
 }
 
 #[allow(unused)]
 impl<'a> ArchiveEntry<'a> {
-    const fn new(filename: TarFormatString<POSIX_1003_MAX_FILENAME_LEN>, data: &'a [u8]) -> Self {
+    const fn new(
+        filename: TarFormatString<POSIX_1003_MAX_FILENAME_LEN>,
+        data: &'a [u8],
+        posix_header: &'a PosixHeader,
+        header_block_index: BlockIndex,
+        raw_blocks: &'a [u8],
+    ) -> Self {
         ArchiveEntry {
             filename,
             data,
             size: data.len(),
+            posix_header,
+            header_block_index,
+            raw_blocks,
+            extension_len: 0,
+            pax: None,
+            long_name: None,
+            long_link_target: None,
         }
     }
 
-    /// Filename of the entry with a maximum of 100 characters (including the
-    /// terminating NULL-byte).
+    /// The header of the entry, for access to the raw metadata.
+    #[must_use]
+    pub const fn posix_header(&self) -> &'a PosixHeader {
+        self.posix_header
+    }
+
+    /// The header block of the entry as it is stored in the archive. See
+    /// [`PosixHeader::as_bytes`].
+    #[must_use]
+    pub const fn header_bytes(&self) -> &'a [u8; BLOCKSIZE] {
+        self.posix_header.as_bytes()
+    }
+
+    /// Filename of the entry with a maximum of 256 characters. The `path`
+    /// record of a PAX extended header takes precedence over the header.
+    ///
+    /// Longer names from a PAX extended header or a GNU long name record
+    /// don't fit. In that case, this silently returns the truncated name of
+    /// the header instead. Use [`Self::filename_with_capacity`] or, with the
+    /// `alloc` feature, `Self::filename_string` to get the untruncated name.
     #[must_use]
     pub const fn filename(&self) -> TarFormatString<{ POSIX_1003_MAX_FILENAME_LEN }> {
         self.filename
     }
 
-    /// Data of the file.
+    /// Whether the filename is safe to use as a relative path during
+    /// extraction. This is not the case for absolute paths and for paths with
+    /// `..` components, as they may point outside of the target directory.
     #[must_use]
-    pub const fn data(&self) -> &'a [u8] {
-        self.data
+    pub fn is_safe_path(&self) -> bool {
+        self.filename.as_str().is_ok_and(|filename| {
+            !filename.starts_with('/') && filename.split('/').all(|component| component != "..")
+        })
     }
 
-    /// Data of the file as string slice, if data is valid UTF-8.
-    #[allow(clippy::missing_const_for_fn)]
-    pub fn data_as_str(&self) -> Result<&'a str, Utf8Error> {
-        core::str::from_utf8(self.data)
+    /// The components of the filename, e.g., `dirA`, `dirB`, and `file.txt`
+    /// for `dirA/dirB/file.txt`. This helps to create the parent directories
+    /// during extraction without allocations. Empty components from leading,
+    /// trailing, or repeated slashes are skipped.
+    ///
+    /// Yields nothing, if the filename isn't valid UTF-8.
+    pub fn path_components(&self) -> impl Iterator<Item = &str> {
+        self.filename
+            .as_str()
+            .unwrap_or_default()
+            .split('/')
+            .filter(|component| !component.is_empty())
     }
 
-    /// Filesize in bytes.
+    /// The filename as native path of the host, i.e., with the platform
+    /// specific separator instead of `/`. Tar always uses `/`, independent of
+    /// the platform that created the archive.
+    ///
+    /// Returns `None`, if the filename isn't valid UTF-8 or if it isn't a safe
+    /// relative path (see [`Self::is_safe_path`]). On Windows, components
+    /// with a `\\` or a `:` are rejected as well.
+    ///
+    /// This is only available with the `std` feature of this crate.
+    #[cfg(feature = "std")]
     #[must_use]
-    pub const fn size(&self) -> usize {
-        self.size
+    pub fn filename_os(&self) -> Option<PathBuf> {
+        if !self.is_safe_path() {
+            return None;
+        }
+        self.filename
+            .as_str()
+            .ok()?
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .map(|component| {
+                let is_invalid = cfg!(windows) && component.contains(['\\', ':']);
+                (!is_invalid).then_some(component)
+            })
+            .collect()
     }
-}
 
-impl Debug for ArchiveEntry<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("ArchiveEntry")
-            .field("filename", &self.filename().as_str())
-            .field("size", &self.size())
-            .field("data", &"<bytes>")
-            .finish()
+    /// Data of the file.
+    #[must_use]
+    pub const fn data(&self) -> &'a [u8] {
+        self.data
     }
-}
-
-/// The data is corrupt and doesn't present a valid Tar archive. Reasons for
-/// that are:
-/// - the data is empty
-/// - the data is not a multiple of 512 (the BLOCKSIZE)
-/// - the data is not at least [`MIN_BLOCK_COUNT`] blocks long
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct CorruptDataError;
 
-impl Display for CorruptDataError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        Debug::fmt(self, f)
+    /// The target of a symlink or a hardlink, as stored in the `linkname`
+    /// field of the header. Returns `None` for other types. See
+    /// [`TarArchiveRef::links`].
+    ///
+    /// Symlinks point to an arbitrary path, which is relative to the
+    /// directory of the link, unless it is absolute. Hardlinks reference a
+    /// previously archived file by its name in the archive.
+    #[must_use]
+    pub fn link_target(&self) -> Option<TarFormatString<NAME_LEN>> {
+        matches!(
+            self.posix_header.typeflag.try_to_type_flag(),
+            Ok(TypeFlag::SYMTYPE | TypeFlag::LINK)
+        )
+        .then_some(self.posix_header.linkname)
     }
-}
 
-#[cfg(feature = "unstable")]
-impl core::error::Error for CorruptDataError {}
+    /// The untruncated name of the entry, if it comes from a GNU long name
+    /// record (typeflag `L`) or from the `path` record of a PAX extended
+    /// header.
+    ///
+    /// Unlike [`Self::filename`], this isn't limited to 256 bytes.
+    #[must_use]
+    pub const fn long_name(&self) -> Option<&'a [u8]> {
+        self.long_name
+    }
 
-/// Type that owns bytes on the heap, that represents a Tar archive.
-/// Unlike [`TarArchiveRef`], this type takes ownership of the data.
-///
-/// This is only available with the `alloc` feature of this crate.
-#[cfg(feature = "alloc")]
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct TarArchive {
-    data: Box<[u8]>,
-}
+    /// The untruncated name, i.e., [`Self::long_name`], if there is one, and
+    /// [`Self::filename`] otherwise.
+    fn full_name(&self) -> &[u8] {
+        self.long_name
+            .unwrap_or_else(|| &self.filename.raw_bytes()[..self.filename.size()])
+    }
 
-#[cfg(feature = "alloc")]
-impl TarArchive {
-    /// Creates a new archive wrapper type. The provided byte array is
-    /// interpreted as bytes in Tar archive format.
+    /// Like [`Self::filename`] but with a capacity of `N` bytes instead of
+    /// 256 bytes, so that long names are not truncated without allocations.
+    /// This trades stack space for the maximum length: each returned name
+    /// occupies `N` bytes. Returns an error, if the name doesn't fit.
     ///
-    /// Returns an error, if the sanity checks report problems.
-    pub fn new(data: Box<[u8]>) -> Result<Self, CorruptDataError> {
-        TarArchiveRef::validate(&data).map(|_| Self { data })
+    /// With the `alloc` feature, [`Self::filename_string`] supports any
+    /// length.
+    pub fn filename_with_capacity<const N: usize>(
+        &self,
+    ) -> Result<TarFormatString<N>, CapacityError> {
+        let name = self.full_name();
+        if name.len() > N {
+            return Err(CapacityError {
+                requested: name.len(),
+                available: N,
+            });
+        }
+        let mut bytes = [0; N];
+        bytes[..name.len()].copy_from_slice(name);
+        Ok(TarFormatString::new(bytes))
     }
 
-    /// Iterates over all entries of the Tar archive.
-    /// Returns items of type [`ArchiveEntry`].
-    /// See also [`ArchiveEntryIterator`].
+    /// The untruncated name of the entry as [`String`]. Invalid UTF-8 is
+    /// replaced by the replacement character.
+    ///
+    /// This is only available with the `alloc` feature of this crate.
+    #[cfg(feature = "alloc")]
     #[must_use]
-    pub fn entries(&self) -> ArchiveEntryIterator {
-        ArchiveEntryIterator::new(self.data.as_ref())
+    pub fn filename_string(&self) -> String {
+        String::from_utf8_lossy(self.full_name()).into_owned()
     }
-}
 
-#[cfg(feature = "alloc")]
-impl From<Box<[u8]>> for TarArchive {
-    fn from(data: Box<[u8]>) -> Self {
-        Self::new(data).unwrap()
+    /// The untruncated link target of the entry, if it comes from a GNU long
+    /// link record (typeflag `K`) or from the `linkpath` record of a PAX
+    /// extended header.
+    ///
+    /// Unlike [`Self::link_target`], this isn't limited to 100 bytes.
+    #[must_use]
+    pub const fn long_link_target(&self) -> Option<&'a [u8]> {
+        self.long_link_target
     }
-}
 
-#[cfg(feature = "alloc")]
-impl From<TarArchive> for Box<[u8]> {
-    fn from(ar: TarArchive) -> Self {
-        ar.data
+    /// The PAX extended header records that apply to the entry, if any. See
+    /// [`TarArchiveRef::entries_with_pax`].
+    #[must_use]
+    pub const fn pax(&self) -> Option<PaxHeader<'a>> {
+        self.pax
     }
-}
-
-/// Wrapper type around bytes, which represents a Tar archive. To iterate the
-/// entries, use [`TarArchiveRef::entries`].
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct TarArchiveRef<'a> {
-    data: &'a [u8],
-}
 
-#[allow(unused)]
-impl<'a> TarArchiveRef<'a> {
-    /// Creates a new archive wrapper type. The provided byte array is
-    /// interpreted as bytes in Tar archive format.
+    /// Modification time in seconds since the Unix epoch. The `mtime` record
+    /// of a PAX extended header takes precedence over the header. Unlike
+    /// [`Self::mtime`], this reports why the `mtime` field of the header
+    /// can't be parsed.
     ///
-    /// Returns an error, if the sanity checks report problems.
-    pub fn new(data: &'a [u8]) -> Result<Self, CorruptDataError> {
-        Self::validate(data).map(|_| Self { data })
+    /// PAX records before 1970 can't be represented, so the header is used
+    /// for them.
+    pub fn modification_time(&self) -> Result<u64, ParseIntError> {
+        self.pax
+            .and_then(|pax| pax.mtime())
+            .and_then(|mtime| u64::try_from(mtime).ok())
+            .map_or_else(|| self.posix_header.mtime.as_number::<u64>(), Ok)
     }
 
-    fn validate(data: &'a [u8]) -> Result<(), CorruptDataError> {
-        let is_malformed = (data.len() % BLOCKSIZE) != 0;
-        let has_min_block_count = data.len() / BLOCKSIZE >= MIN_BLOCK_COUNT;
-        (!data.is_empty() && !is_malformed && has_min_block_count)
-            .then_some(())
-            .ok_or(CorruptDataError)
+    /// Modification time in seconds since the Unix epoch. The `mtime` record
+    /// of a PAX extended header takes precedence over the header.
+    #[must_use]
+    pub fn mtime(&self) -> Option<i64> {
+        self.pax
+            .and_then(|pax| pax.mtime())
+            .or_else(|| self.posix_header.mtime.as_number().ok())
     }
 
-    /// Creates an [`ArchiveEntryIterator`].
+    /// Numeric user ID of the owner. The `uid` record of a PAX extended
+    /// header takes precedence over the header.
     #[must_use]
-    pub fn entries(&self) -> ArchiveEntryIterator {
-        ArchiveEntryIterator::new(self.data)
+    pub fn uid(&self) -> Option<u64> {
+        self.pax
+            .and_then(|pax| pax.uid())
+            .or_else(|| self.posix_header.uid.as_number().ok())
     }
-}
-
-/// Iterates over the headers of the Tar archive.
-#[derive(Debug)]
-pub struct ArchiveHeaderIterator<'a> {
-    archive_data: &'a [u8],
-    next_hdr_block_index: usize,
-}
 
-impl<'a> ArchiveHeaderIterator<'a> {
+    /// Numeric group ID of the owner. The `gid` record of a PAX extended
+    /// header takes precedence over the header.
     #[must_use]
-    pub fn new(archive: &'a [u8]) -> Self {
-        assert!(!archive.is_empty());
-        assert_eq!(archive.len() % BLOCKSIZE, 0);
-        Self {
-            archive_data: archive,
-            next_hdr_block_index: 0,
-        }
+    pub fn gid(&self) -> Option<u64> {
+        self.pax
+            .and_then(|pax| pax.gid())
+            .or_else(|| self.posix_header.gid.as_number().ok())
     }
 
-    /// Parse the memory at the given block as [`PosixHeader`].
-    fn block_as_header(&self, block_index: usize) -> &'a PosixHeader {
-        unsafe {
-            self.archive_data
-                .as_ptr()
-                .add(block_index * BLOCKSIZE)
-                .cast::<PosixHeader>()
-                .as_ref()
-                .unwrap()
-        }
+    /// Name of the owning user, as stored in the `uname` field of the header.
+    pub fn user_name(&self) -> Result<&'a str, Utf8Error> {
+        self.posix_header.uname.as_str()
     }
-}
-
-type BlockIndex = usize;
 
-impl<'a> Iterator for ArchiveHeaderIterator<'a> {
-    type Item = (BlockIndex, &'a PosixHeader);
+    /// Name of the owning group, as stored in the `gname` field of the header.
+    pub fn group_name(&self) -> Result<&'a str, Utf8Error> {
+        self.posix_header.gname.as_str()
+    }
 
-    /// Returns the next header. Internally, it updates the necessary data
-    /// structures to not read the same header multiple times.
+    /// Raw pointer to and length of the data of the file within the archive.
     ///
-    /// This returns `None` if either no further headers are found or if a
-    /// header can't be parsed.
-    fn next(&mut self) -> Option<Self::Item> {
-        let total_block_count = self.archive_data.len() / BLOCKSIZE;
-        if self.next_hdr_block_index >= total_block_count {
-            warn!("Invalid block index. Probably the Tar is corrupt: an header had an invalid payload size");
-            return None;
-        }
+    /// This is an escape hatch for low-level code that must construct its own
+    /// view of the data, e.g., a `&[MaybeUninit<u8>]` or a volatile view for
+    /// a DMA transfer. Prefer [`Self::data`] whenever possible. The pointer is
+    /// only valid as long as the archive data lives and it must not be used
+    /// for writes.
+    #[must_use]
+    pub const fn data_ptr_len(&self) -> (*const u8, usize) {
+        (self.data.as_ptr(), self.data.len())
+    }
 
-        let hdr = self.block_as_header(self.next_hdr_block_index);
-        let block_index = self.next_hdr_block_index;
+    /// The raw bytes of the entry in the archive, i.e., the header block and
+    /// the data blocks including the padding of the last block.
+    ///
+    /// This can be used to copy entries into a new archive without
+    /// serializing the header again. Note that the extension records that
+    /// precede the entry, i.e., PAX extended headers and GNU long names and
+    /// long link targets, are not included. See
+    /// [`Self::raw_blocks_with_extensions`].
+    #[must_use]
+    pub const fn raw_blocks(&self) -> &'a [u8] {
+        self.raw_blocks.split_at(self.extension_len).1
+    }
 
-        // Start at next block on next iteration.
-        self.next_hdr_block_index += 1;
+    /// Like [`Self::raw_blocks`] but including the blocks of the extension
+    /// records that precede the header, i.e., PAX extended headers and GNU
+    /// long names and long link targets. Copy these to preserve long names.
+    ///
+    /// PAX global headers apply to all following entries and are not
+    /// included.
+    #[must_use]
+    pub const fn raw_blocks_with_extensions(&self) -> &'a [u8] {
+        self.raw_blocks
+    }
 
-        // We only update the block index for types that have a payload.
-        // In directory entries, for example, the size field has other
-        // semantics. See spec.
-        if let Ok(typeflag) = hdr.typeflag.try_to_type_flag() {
-            if typeflag.is_regular_file() {
-                let payload_block_count = hdr
-                    .payload_block_count()
-                    .inspect_err(|e| {
-                        log::error!("Unparsable size ({e:?}) in header {hdr:#?}");
-                    })
-                    .ok()?;
-                self.next_hdr_block_index += payload_block_count;
-            }
+    /// Returns a cursor over the data of the file in blocks of 512 bytes.
+    /// See [`DataReader`].
+    #[must_use]
+    pub fn data_reader(&self) -> DataReader<'a> {
+        let blocks = self.raw_blocks().get(BLOCKSIZE..).unwrap_or_default();
+        DataReader {
+            blocks,
+            len: self.data.len().min(blocks.len()),
+            offset: 0,
         }
-
-        Some((block_index, hdr))
     }
-}
 
-impl ExactSizeIterator for ArchiveEntryIterator<'_> {}
+    /// Copies the data of the file to the beginning of `dst` and returns the
+    /// number of copied bytes, e.g., to load a file into memory that was
+    /// reserved upfront. Returns an error, if `dst` is smaller than the file.
+    pub fn copy_data_into(&self, dst: &mut [u8]) -> Result<usize, BufferTooSmallError> {
+        if dst.len() < self.data.len() {
+            return Err(BufferTooSmallError {
+                required: self.data.len(),
+                available: dst.len(),
+            });
+        }
+        dst[..self.data.len()].copy_from_slice(self.data);
+        Ok(self.data.len())
+    }
 
-/// Iterator over the files of the archive.
-///
-/// Only regular files are supported, but not directories, links, or other
-/// special types ([`crate::TypeFlag`]). The full path to files is reflected
-/// in their file name.
-#[derive(Debug)]
-pub struct ArchiveEntryIterator<'a>(ArchiveHeaderIterator<'a>);
+    /// Data of the file as slice of `T`. This is useful to interpret the
+    /// payload as structured data without copying it.
+    ///
+    /// Returns `None`, if the data isn't aligned for `T` or if its length
+    /// isn't a multiple of the size of `T`. As the data of each entry starts
+    /// at a block boundary, an alignment of up to 512 bytes is satisfied if
+    /// the archive itself is aligned accordingly.
+    #[must_use]
+    pub fn data_aligned<T: FromBytes>(&self) -> Option<&'a [T]> {
+        let size = core::mem::size_of::<T>();
+        let is_valid = size != 0
+            && self.data.len() % size == 0
+            && self.data.as_ptr().align_offset(core::mem::align_of::<T>()) == 0;
+        // SAFETY: Pointer and length are valid for `T` and `T` allows any bit
+        // pattern.
+        is_valid.then(|| unsafe {
+            core::slice::from_raw_parts(self.data.as_ptr().cast::<T>(), self.data.len() / size)
+        })
+    }
 
-impl<'a> ArchiveEntryIterator<'a> {
-    fn new(archive: &'a [u8]) -> Self {
-        Self(ArchiveHeaderIterator::new(archive))
+    /// Data of the file as string slice, if data is valid UTF-8.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn data_as_str(&self) -> Result<&'a str, Utf8Error> {
+        core::str::from_utf8(self.data)
     }
 
-    fn next_hdr(&mut self) -> Option<(BlockIndex, &'a PosixHeader)> {
-        self.0.next()
+    /// Filesize in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
     }
-}
 
-impl<'a> Iterator for ArchiveEntryIterator<'a> {
-    type Item = ArchiveEntry<'a>;
+    /// Whether the payload is shorter than the size declared by the header,
+    /// as the archive ends within the payload. This only happens for archives
+    /// opened with [`TarArchiveRef::new_lenient`].
+    #[must_use]
+    pub fn is_truncated(&self) -> bool {
+        let declared_size = self
+            .pax
+            .and_then(|pax| pax.size())
+            .or_else(|| self.posix_header.size.as_number::<u64>().ok());
+        declared_size.is_some_and(|size| (self.data.len() as u64) < size)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let (mut block_index, mut hdr) = self.next_hdr()?;
+    /// SHA-256 digest of the data of the file.
+    ///
+    /// This is only available with the `sha2` feature of this crate.
+    #[cfg(feature = "sha2")]
+    #[must_use]
+    pub fn sha256(&self) -> [u8; 32] {
+        sha256(self.data)
+    }
 
-        // Ignore directory entries, i.e. yield only regular files. Works as
-        // filenames in tarballs are fully specified, e.g. dirA/dirB/file1
-        while !hdr
-            .typeflag
-            .try_to_type_flag()
-            .inspect_err(|e| error!("Invalid TypeFlag: {e:?}"))
-            .ok()?
-            .is_regular_file()
-        {
-            warn!(
-                "Skipping entry of type {:?} (not supported yet)",
-                hdr.typeflag
-            );
+    /// CRC-32 (IEEE) checksum of the data of the file, as printed by
+    /// `crc32` or `7z h`. This is much cheaper than [`Self::sha256`] but not
+    /// cryptographic, so only use it to detect accidental corruption, e.g.,
+    /// against a build manifest.
+    ///
+    /// This is only available with the `crc` feature of this crate.
+    #[cfg(feature = "crc")]
+    #[must_use]
+    pub fn payload_crc32(&self) -> u32 {
+        crc32fast::hash(self.data)
+    }
 
-            // Update properties.
-            (block_index, hdr) = self.next_hdr()?;
-        }
+    /// For directories, this returns the `size` field of the header, which
+    /// is the maximum number of bytes that the directory may hold on systems
+    /// that support such limits. Returns `None` for all other entries.
+    ///
+    /// The value is advisory and usually zero, which means that there is no
+    /// limit. Note that directory entries are currently not yielded by
+    /// [`TarArchiveRef::entries`].
+    #[must_use]
+    pub fn directory_size_limit(&self) -> Option<u64> {
+        let is_dir = self.posix_header.typeflag.try_to_type_flag() == Ok(TypeFlag::DIRTYPE);
+        is_dir
+            .then(|| self.posix_header.size.as_number().ok())
+            .flatten()
+    }
 
-        // check if we found end of archive (two zero blocks)
-        if hdr.is_zero_block() {
-            if self.next_hdr()?.1.is_zero_block() {
-                // found end
-                return None;
-            } else {
-                panic!("Never expected to have a situation where self.next_hdr() returns a zero block and the next one is not a zero block, as we should never point to an 'end zero block of a regular file'");
-            }
+    /// For continuations of a file in a GNU multi-volume archive (typeflag
+    /// `M`), this returns the offset in the file at which the data of this
+    /// entry resumes. Returns `None` for all other entries.
+    ///
+    /// Joining the parts of a file across volumes is out of scope of this
+    /// crate. The [`Self::data`] of such an entry is only the part that is
+    /// stored in this volume.
+    #[must_use]
+    pub fn volume_offset(&self) -> Option<u64> {
+        if self.posix_header.typeflag.as_byte() != b'M' {
+            return None;
         }
-
-        let payload_size: usize = hdr
-            .size
+        // In the GNU header, the prefix field holds atime, ctime, and offset.
+        let offset = &self.posix_header.prefix.as_bytes()[24..36];
+        TarFormatOctal::<12>::new(offset.try_into().unwrap())
             .as_number()
-            .inspect_err(|e| error!("Can't parse the file size from the header. {e:#?}"))
-            .ok()?;
+            .ok()
+    }
 
-        let idx_first_data_block = block_index + 1;
-        let idx_begin = idx_first_data_block * BLOCKSIZE;
-        let idx_end_exclusive = idx_begin + payload_size;
+    /// The raw bytes of the octal `size` field of the header. This helps to
+    /// debug archives whose size fields are written in unexpected ways.
+    #[must_use]
+    pub const fn raw_size_field(&self) -> &'a [u8; 12] {
+        self.posix_header.size.as_inner().as_bytes()
+    }
 
-        // This doesn't subtract with overflow as we ensured a minimum size in
-        // the constructor.
-        let max_data_end_index_exclusive = self.0.archive_data.len() - 2 * BLOCKSIZE;
-        if idx_end_exclusive >= max_data_end_index_exclusive {
-            warn!("Invalid Tar. The size of the payload ({payload_size}) is larger than what is valid");
-            return None;
+    /// How the `size` field of the header is encoded. Tools that re-emit an
+    /// archive can use this to preserve the original encoding.
+    #[must_use]
+    pub const fn size_encoding(&self) -> SizeEncoding {
+        if self.posix_header.size.is_base256() {
+            SizeEncoding::Base256
+        } else {
+            SizeEncoding::Octal
         }
+    }
 
-        let file_bytes = &self.0.archive_data[idx_begin..idx_end_exclusive];
+    /// Writes a line in the style of `tar -tv` for the entry, such as
+    /// `-rw-r--r-- user/group    1234 2023-05-01 12:00 path/to/file`.
+    ///
+    /// The line consists of the type, the permissions, the owner and group,
+    /// the size, the modification time in UTC, and the filename. Numeric IDs
+    /// are used, if the names are missing. Like GNU tar, the owner, group, and
+    /// size are padded to a width of at least 19 characters. No newline is
+    /// written.
+    pub fn long_listing_line(&self, out: &mut impl core::fmt::Write) -> core::fmt::Result {
+        let hdr = self.posix_header;
+        let type_char = match hdr.typeflag.as_byte() {
+            b'1' => 'h',
+            b'2' => 'l',
+            b'3' => 'c',
+            b'4' => 'b',
+            b'5' => 'd',
+            b'6' => 'p',
+            _ => '-',
+        };
+        out.write_char(type_char)?;
+        let permissions = hdr
+            .mode
+            .to_flags()
+            .map_or(*b"?????????", ModeFlags::to_symbolic);
+        permissions
+            .iter()
+            .try_for_each(|&symbol| out.write_char(char::from(symbol)))?;
 
-        let mut filename: TarFormatString<256> =
-            TarFormatString::<POSIX_1003_MAX_FILENAME_LEN>::new([0; POSIX_1003_MAX_FILENAME_LEN]);
+        let owner = OwnerName::new(&hdr.uname, &hdr.uid);
+        let group = OwnerName::new(&hdr.gname, &hdr.gid);
+        let width = 18_usize.saturating_sub(owner.len() + 1 + group.len());
+        let mtime = self.mtime().and_then(|mtime| u64::try_from(mtime).ok());
+        let (year, month, day, hour, minute) = utc_date_time(mtime.unwrap_or(0));
+        write!(
+            out,
+            " {owner}/{group} {:>width$} {year:04}-{month:02}-{day:02} {hour:02}:{minute:02} ",
+            self.size
+        )?;
 
-        // POXIS_1003 long filename check
-        // https://docs.scinet.utoronto.ca/index.php/(POSIX_1003.1_USTAR)
-        if (
-            hdr.magic.as_str(),
-            hdr.version.as_str(),
-            hdr.prefix.is_empty(),
-        ) == (Ok("ustar"), Ok("00"), false)
-        {
-            filename.append(&hdr.prefix);
-            filename.append(&TarFormatString::<1>::new([b'/']));
+        match self.filename.as_str() {
+            Ok(filename) => out.write_str(filename),
+            Err(e) => {
+                // Write the valid part, as there is no lossy conversion in core.
+                let valid = &self.filename.as_bytes()[..e.valid_up_to()];
+                out.write_str(core::str::from_utf8(valid).unwrap_or_default())?;
+                out.write_char(char::REPLACEMENT_CHARACTER)
+            }
         }
-        filename.append(&hdr.name);
-        Some(ArchiveEntry::new(filename, file_bytes))
     }
 }
 
-#[cfg(test)]
+/// The name of the owner or group of an entry, or its numeric ID, if the name
+/// is missing.
+enum OwnerName<'a> {
+    Name(&'a str),
+    Id(u64),
+}
+
+impl<'a> OwnerName<'a> {
+    fn new(name: &'a TarFormatString<32>, id: &TarFormatOctal<8>) -> Self {
+        match name.as_str() {
+            Ok(name) if !name.is_empty() => Self::Name(name),
+            _ => Self::Id(id.as_number().unwrap_or(0)),
+        }
+    }
+
+    /// Length of the displayed name in characters.
+    fn len(&self) -> usize {
+        match self {
+            Self::Name(name) => name.chars().count(),
+            Self::Id(id) => id.checked_ilog10().map_or(1, |digits| digits as usize + 1),
+        }
+    }
+}
+
+impl Display for OwnerName<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Id(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// Converts seconds since the Unix epoch into the UTC date and time as
+/// `(year, month, day, hour, minute)`.
+///
+/// Based on the `civil_from_days` algorithm by Howard Hinnant.
+const fn utc_date_time(secs: u64) -> (u64, u64, u64, u64, u64) {
+    let days = secs / 86400;
+    let secs_of_day = secs % 86400;
+    // Shift the epoch to 0000-03-01, so that leap days are at the end of a
+    // year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+    )
+}
+
+/// Parses a device number field with the given radix. Empty fields are `0`.
+fn parse_device_number(field: &TarFormatOctal<8>, radix: u32) -> Result<u64, ParseIntError> {
+    let field = field.as_inner();
+    if field.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(field.as_digits().unwrap_or(""), radix)
+}
+
+/// Parses the device numbers of character and block devices. With `lenient`,
+/// each field that isn't a valid octal number is parsed as hexadecimal. See
+/// [`RawEntry::device_numbers`].
+fn device_numbers(hdr: &PosixHeader, lenient: bool) -> Option<(u64, u64)> {
+    let is_device = matches!(
+        hdr.typeflag.try_to_type_flag(),
+        Ok(TypeFlag::CHRTYPE | TypeFlag::BLKTYPE)
+    );
+    if !is_device {
+        return None;
+    }
+    let parse = |field| {
+        parse_device_number(field, 8)
+            .or_else(|e| {
+                if lenient {
+                    parse_device_number(field, 16)
+                } else {
+                    Err(e)
+                }
+            })
+            .ok()
+    };
+    Some((parse(&hdr.dev_major)?, parse(&hdr.dev_minor)?))
+}
+
+impl Debug for ArchiveEntry<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ArchiveEntry")
+            .field("filename", &self.filename().as_str())
+            .field("size", &self.size())
+            .field("mtime", &self.mtime().map(UnixTimestamp))
+            .field("data", &"<bytes>")
+            .finish()
+    }
+}
+
+/// Prints the entry in the style of `tar -tv`. See
+/// [`ArchiveEntry::long_listing_line`].
+impl Display for ArchiveEntry<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.long_listing_line(f)
+    }
+}
+
+/// Serializes the filename, the size, and the most important metadata of
+/// the header. PAX extended headers are respected.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ArchiveEntry<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut entry = serializer.serialize_struct("ArchiveEntry", 7)?;
+        entry.serialize_field("filename", &self.filename)?;
+        entry.serialize_field("size", &self.size)?;
+        entry.serialize_field("typeflag", &self.posix_header.typeflag)?;
+        entry.serialize_field("mode", &self.posix_header.mode)?;
+        entry.serialize_field("mtime", &self.mtime())?;
+        entry.serialize_field("uid", &self.uid())?;
+        entry.serialize_field("gid", &self.gid())?;
+        entry.end()
+    }
+}
+
+/// Cursor over the data of an [`ArchiveEntry`] in blocks of 512 bytes. See
+/// [`ArchiveEntry::data_reader`].
+///
+/// This is zero-copy like [`ArchiveEntry::data`], but it allows to process
+/// large files block by block, e.g., to feed a DMA engine. The last block may
+/// be only partially filled with data, see [`Self::last_block_valid_len`].
+#[derive(Clone, Debug)]
+pub struct DataReader<'a> {
+    /// The data blocks including the padding of the last block.
+    blocks: &'a [u8],
+    len: usize,
+    offset: usize,
+}
+
+impl<'a> DataReader<'a> {
+    /// Returns the next block or `None`, if all data was read. This is also
+    /// `None` for the incomplete last block of an archive that was opened
+    /// with [`TarArchiveRef::new_lenient`]; use [`ArchiveEntry::data`] to
+    /// read such partial data.
+    pub fn next_block(&mut self) -> Option<&'a [u8; BLOCKSIZE]> {
+        if self.offset >= self.len {
+            return None;
+        }
+        let block = self.blocks.get(self.offset..)?.get(..BLOCKSIZE)?;
+        let block = block.try_into().ok()?;
+        self.offset += BLOCKSIZE;
+        Some(block)
+    }
+
+    /// Number of bytes of data that were not yet returned.
+    #[must_use]
+    pub const fn remaining_bytes(&self) -> usize {
+        self.len.saturating_sub(self.offset)
+    }
+
+    /// Number of bytes of data in the last block. The rest of the block is
+    /// padding. This is `0` for empty files.
+    #[must_use]
+    pub const fn last_block_valid_len(&self) -> usize {
+        match self.len % BLOCKSIZE {
+            0 if self.len > 0 => BLOCKSIZE,
+            len => len,
+        }
+    }
+}
+
+impl<'a> Iterator for DataReader<'a> {
+    type Item = &'a [u8; BLOCKSIZE];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block()
+    }
+}
+
+/// Encoding of a numeric header field, as reported by
+/// [`ArchiveEntry::size_encoding`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SizeEncoding {
+    /// ASCII octal digits, as in all Tar formats.
+    Octal,
+    /// The base-256 encoding of GNU Tar for values that don't fit into the
+    /// octal digits. The highest bit of the first byte is set and the
+    /// remaining bytes hold the big-endian value.
+    Base256,
+}
+
+/// Marker for types that can be read from the data of an [`ArchiveEntry`]
+/// via [`ArchiveEntry::data_aligned`].
+///
+/// # Safety
+///
+/// Every bit pattern must be a valid value of the type and the type must not
+/// have padding bytes, e.g., a `#[repr(C)]` struct that only consists of
+/// integers without gaps.
+pub unsafe trait FromBytes: Copy {}
+
+macro_rules! impl_from_bytes {
+    ($($ty:ty),*) => {
+        $(unsafe impl FromBytes for $ty {})*
+    };
+}
+
+impl_from_bytes!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+
+/// Result of [`TarArchiveRef::check_names`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NameCheck {
+    /// Two consecutive files have the same name.
+    pub has_duplicates: bool,
+    /// The files are sorted by their name.
+    pub is_sorted: bool,
+}
+
+/// Number of headers per type, as returned by [`TarArchiveRef::inspect`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveStats {
+    counts: [usize; TypeFlag::ALL.len()],
+    invalid_count: usize,
+}
+
+impl ArchiveStats {
+    /// Number of headers per type, in the order of [`TypeFlag::ALL`].
+    #[must_use]
+    pub const fn counts_by_type(&self) -> [usize; TypeFlag::ALL.len()] {
+        self.counts
+    }
+
+    /// Number of headers of the given type.
+    #[must_use]
+    pub const fn get(&self, typeflag: TypeFlag) -> usize {
+        self.counts[typeflag.index()]
+    }
+
+    /// Number of headers with an invalid typeflag.
+    #[must_use]
+    pub const fn invalid_count(&self) -> usize {
+        self.invalid_count
+    }
+}
+
+/// Location of an entry in an archive, as written by
+/// [`TarArchiveRef::toc_into`].
+///
+/// It only consists of offsets and doesn't borrow the archive.
+/// [`TarArchiveRef::entry_at`] turns it back into an [`ArchiveEntry`]. The
+/// location includes the [`ParserState`] before the entry, so that GNU long
+/// names and PAX extended headers are applied again.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TocEntry {
+    header_offset: usize,
+    size: usize,
+    state: ParserState,
+}
+
+impl TocEntry {
+    /// Byte offset of the header of the entry in the archive.
+    #[must_use]
+    pub const fn header_offset(&self) -> usize {
+        self.header_offset
+    }
+
+    /// Byte offset of the data of the entry in the archive.
+    #[must_use]
+    pub const fn data_offset(&self) -> usize {
+        self.header_offset + BLOCKSIZE
+    }
+
+    /// Filesize in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// The data is corrupt and doesn't present a valid Tar archive. Reasons for
+/// that are:
+/// - the data is empty
+/// - the data is not a multiple of 512 (the BLOCKSIZE)
+/// - the data is not at least [`MIN_BLOCK_COUNT`] blocks long
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CorruptDataError;
+
+impl Display for CorruptDataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for CorruptDataError {}
+
+/// The buffer is too small for the data of the entry. See
+/// [`ArchiveEntry::copy_data_into`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BufferTooSmallError {
+    /// Size of the data in bytes.
+    pub required: usize,
+    /// Size of the buffer in bytes.
+    pub available: usize,
+}
+
+impl Display for BufferTooSmallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "the buffer of {} bytes is too small for {} bytes",
+            self.available, self.required
+        )
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for BufferTooSmallError {}
+
+/// Compression formats that are detected by [`Compression::detect`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip, as in `.tar.gz`.
+    Gzip,
+    /// Zstandard, as in `.tar.zst`.
+    Zstd,
+    /// xz, as in `.tar.xz`.
+    Xz,
+    /// bzip2, as in `.tar.bz2`.
+    Bzip2,
+}
+
+impl Compression {
+    /// Detects a compressed stream by the magic bytes at its beginning.
+    #[must_use]
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        const MAGICS: [(&[u8], Compression); 4] = [
+            (&[0x1f, 0x8b], Compression::Gzip),
+            (&[0x28, 0xb5, 0x2f, 0xfd], Compression::Zstd),
+            (&[0xfd, 0x37, 0x7a], Compression::Xz),
+            (b"BZh", Compression::Bzip2),
+        ];
+        MAGICS
+            .iter()
+            .find(|(magic, _)| data.starts_with(magic))
+            .map(|(_, compression)| *compression)
+    }
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Xz => "xz",
+            Self::Bzip2 => "bzip2",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Errors that may happen when creating an archive or when iterating its
+/// entries with `try_entries`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TarError {
+    /// The data doesn't present a valid Tar archive.
+    CorruptData(CorruptDataError),
+    /// The data is compressed and must be decompressed first.
+    CompressedInput {
+        /// The detected compression.
+        kind: Compression,
+    },
+    /// The declared size of a payload exceeds the remaining length of the
+    /// archive or can't be addressed on this platform, e.g., a file of 6 GiB
+    /// on a 32-bit system.
+    PayloadTooLarge {
+        /// The declared size in bytes.
+        size: u64,
+    },
+    /// The checksum of a header is wrong. See
+    /// [`TarArchiveRef::new_validated`].
+    InvalidChecksum {
+        /// The index of the block of the header.
+        block_index: usize,
+        /// The stored and the computed checksum.
+        error: ChecksumError,
+    },
+    /// The typeflag of a header is unknown. See [`crate::TypeFlag`].
+    InvalidTypeFlag {
+        /// The index of the block of the header.
+        block_index: usize,
+        /// The raw typeflag.
+        typeflag: u8,
+    },
+    /// The size field of a header can't be parsed.
+    UnparsableSize {
+        /// The index of the block of the header.
+        block_index: usize,
+    },
+    /// The archive ends without the terminating zero blocks, e.g., as it was
+    /// truncated.
+    MissingTerminator,
+}
+
+impl Display for TarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::CorruptData(e) => Display::fmt(e, f),
+            Self::CompressedInput { kind } => {
+                write!(f, "the data is compressed with {kind}; decompress it first")
+            }
+            Self::PayloadTooLarge { size } => {
+                write!(
+                    f,
+                    "the payload of {size} bytes doesn't fit into the archive"
+                )
+            }
+            Self::InvalidChecksum { block_index, error } => {
+                write!(f, "invalid header in block {block_index}: {error}")
+            }
+            Self::InvalidTypeFlag {
+                block_index,
+                typeflag,
+            } => {
+                write!(
+                    f,
+                    "invalid typeflag {:?} in block {block_index}",
+                    char::from(*typeflag)
+                )
+            }
+            Self::UnparsableSize { block_index } => {
+                write!(f, "unparsable size field in block {block_index}")
+            }
+            Self::MissingTerminator => {
+                write!(f, "the archive ends without the terminating zero blocks")
+            }
+        }
+    }
+}
+
+impl From<CorruptDataError> for TarError {
+    fn from(e: CorruptDataError) -> Self {
+        Self::CorruptData(e)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for TarError {}
+
+/// A problem that [`TarArchiveRef::validate_full`] found, together with the
+/// block index of the offending header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FullValidationError {
+    /// The index of the block of the offending header.
+    pub block_index: usize,
+    /// The problem with the header.
+    pub error: TarError,
+}
+
+impl Display for FullValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "block {}: {}", self.block_index, self.error)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for FullValidationError {}
+
+/// Type that owns bytes on the heap, that represents a Tar archive.
+/// Unlike [`TarArchiveRef`], this type takes ownership of the data.
+///
+/// This is only available with the `alloc` feature of this crate.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TarArchive {
+    data: Box<[u8]>,
+}
+
+#[cfg(feature = "alloc")]
+impl TarArchive {
+    /// Creates a new archive wrapper type. The provided byte array is
+    /// interpreted as bytes in Tar archive format.
+    ///
+    /// Returns an error, if the sanity checks report problems.
+    pub fn new(data: Box<[u8]>) -> Result<Self, TarError> {
+        TarArchiveRef::validate(&data).map(|_| Self { data })
+    }
+
+    /// Moves the data into a [`SharedTarArchive`], whose entries can share
+    /// ownership of the data. This copies the data once, as the reference
+    /// count must be stored in the same allocation.
+    #[cfg(target_has_atomic = "ptr")]
+    #[must_use]
+    pub fn into_shared(self) -> SharedTarArchive {
+        SharedTarArchive {
+            data: self.data.into(),
+        }
+    }
+
+    /// Consumes the archive and yields each file as [`OwnedArchiveEntry`],
+    /// which doesn't borrow from the archive. This is convenient, if the
+    /// archive itself is only a temporary value.
+    ///
+    /// The header and the data of each entry are copied, which costs one
+    /// allocation per entry. See [`SharedTarArchive::entries_shared`] for a
+    /// variant that shares the data of the archive instead.
+    #[must_use]
+    pub const fn into_entries(self) -> OwnedEntryIterator {
+        OwnedEntryIterator {
+            archive: self,
+            state: ParserState::new(),
+        }
+    }
+
+    /// Like [`Self::into_entries`] but collects the files into a [`Vec`].
+    #[must_use]
+    pub fn into_entries_owned(self) -> Vec<OwnedArchiveEntry> {
+        self.into_entries().collect()
+    }
+
+    /// Iterates over all entries of the Tar archive.
+    /// Returns items of type [`ArchiveEntry`].
+    /// See also [`ArchiveEntryIterator`].
+    #[must_use]
+    pub fn entries(&self) -> ArchiveEntryIterator<'_> {
+        ArchiveEntryIterator::new(ArchiveHeaderIterator::new(self.data.as_ref()))
+    }
+
+    /// Returns the number of entries that [`Self::entries`] yields. This
+    /// walks all headers of the archive once.
+    #[must_use]
+    pub fn entry_count(&self) -> usize {
+        self.entries().count()
+    }
+
+    /// Returns `true`, if [`Self::entries`] yields no entries, e.g., because
+    /// the archive only contains directories.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries().next().is_none()
+    }
+
+    /// Resumes an iteration at a position that was saved with
+    /// [`ArchiveEntryIterator::save`]. See [`ParserState`].
+    #[must_use]
+    pub fn restore(&self, state: ParserState) -> ArchiveEntryIterator<'_> {
+        ArchiveEntryIterator::with_state(ArchiveHeaderIterator::new(self.data.as_ref()), state)
+    }
+
+    /// Resumes an iteration at the header at the given block index, e.g.,
+    /// one that was returned by [`ArchiveEntryIterator::current_block`].
+    /// Returns `None`, if the block index is beyond the archive.
+    ///
+    /// Unlike [`Self::restore`], this doesn't restore a pending PAX extended
+    /// header, so prefer [`ParserState`] for archives that use them.
+    #[must_use]
+    pub fn entries_from_block(&self, block_index: usize) -> Option<ArchiveEntryIterator<'_>> {
+        let data: &[u8] = self.data.as_ref();
+        (block_index < data.len() / BLOCKSIZE).then(|| {
+            let state = ParserState {
+                next_hdr_block_index: block_index,
+                ..ParserState::new()
+            };
+            ArchiveEntryIterator::with_state(ArchiveHeaderIterator::new(data), state)
+        })
+    }
+
+    /// Like [`Self::entries`] but each entry is paired with the PAX extended
+    /// header records that apply to it, if there are any. This includes the
+    /// records of the most recent global extended header.
+    #[must_use]
+    pub fn entries_with_pax(&self) -> PaxEntryIterator<'_> {
+        PaxEntryIterator(self.entries())
+    }
+
+    /// Iterates over the symlinks and hardlinks of the archive, which are
+    /// skipped by [`Self::entries`]. Use [`ArchiveEntry::link_target`] to get
+    /// their targets.
+    #[must_use]
+    pub fn links(&self) -> LinkIterator<'_> {
+        LinkIterator(ArchiveHeaderIterator::new(self.data.as_ref()).logical_entries())
+    }
+
+    /// Iterates over all entries of the archive, independent of their type,
+    /// including directories, links, FIFOs, and devices. Unlike
+    /// [`Self::entries`], this neither applies PAX extended headers nor GNU
+    /// long names, which are yielded as entries themselves. See [`RawEntry`].
+    #[must_use]
+    pub fn raw_entries(&self) -> RawEntryIterator<'_> {
+        RawEntryIterator(ArchiveHeaderIterator::new(self.data.as_ref()))
+    }
+
+    /// Like [`Self::entries`] but yields an error instead of silently ending
+    /// the iteration, if the archive is malformed, e.g., if the payload of an
+    /// entry doesn't fit into the archive or if a typeflag is invalid. This
+    /// distinguishes a clean end of the archive from a corrupt one. The
+    /// iteration ends after an error.
+    #[must_use]
+    pub fn try_entries(&self) -> TryEntryIterator<'_> {
+        TryEntryIterator(self.entries())
+    }
+
+    /// Like [`Self::entries`] but stops with [`StopReason::SizeLimitReached`],
+    /// once the cumulative size of the yielded entries would exceed
+    /// `max_total` bytes. See [`SizeLimitedEntryIterator`].
+    #[must_use]
+    pub fn entries_size_limited(&self, max_total: u64) -> SizeLimitedEntryIterator<'_> {
+        SizeLimitedEntryIterator::new(self.entries(), max_total)
+    }
+
+    /// Like [`Self::entries`] but calls `should_continue` before each entry
+    /// and stops with [`StopReason::Cancelled`], once it returns `false`.
+    /// See [`CancellableEntryIterator`].
+    pub fn entries_until<F: FnMut() -> bool>(
+        &self,
+        should_continue: F,
+    ) -> CancellableEntryIterator<'_, F> {
+        CancellableEntryIterator::new(self.entries(), should_continue)
+    }
+
+    /// Splits the archive into one archive per top-level directory, i.e., per
+    /// first component of the path of the files. Files without a directory
+    /// component are grouped under `""`. The path is the untruncated name
+    /// after PAX extended headers and GNU long names are applied.
+    ///
+    /// The entries are copied verbatim together with their PAX extended
+    /// headers and GNU long names (see
+    /// [`ArchiveEntry::raw_blocks_with_extensions`]) and each archive gets
+    /// its own terminator. PAX global headers are not copied.
+    #[must_use]
+    pub fn split_by_top_dir(&self) -> BTreeMap<String, Self> {
+        let mut groups = BTreeMap::<String, Vec<u8>>::new();
+        for entry in self.entries() {
+            let filename = entry.full_name();
+            let top_dir = filename
+                .iter()
+                .position(|byte| *byte == b'/')
+                .map_or(&[][..], |index| &filename[..index]);
+            groups
+                .entry(String::from_utf8_lossy(top_dir).into_owned())
+                .or_default()
+                .extend_from_slice(entry.raw_blocks_with_extensions());
+        }
+        groups
+            .into_iter()
+            .map(|(top_dir, mut data)| {
+                let len = (data.len() + 2 * BLOCKSIZE).max(MIN_BLOCK_COUNT * BLOCKSIZE);
+                data.resize(len, 0);
+                let archive = Self {
+                    data: data.into_boxed_slice(),
+                };
+                (top_dir, archive)
+            })
+            .collect()
+    }
+
+    /// Returns the byte range of the data of each file in the archive
+    /// together with its filename.
+    ///
+    /// The ranges don't overlap. Hence, the slices `&archive[range]` can be
+    /// borrowed and processed concurrently, e.g., with `par_iter` of
+    /// `rayon`, although this crate itself is single-threaded.
+    #[must_use]
+    pub fn entry_ranges(
+        &self,
+    ) -> Vec<(Range<usize>, TarFormatString<POSIX_1003_MAX_FILENAME_LEN>)> {
+        self.entries()
+            .map(|entry| {
+                let begin = (entry.header_block_index + 1) * BLOCKSIZE;
+                (begin..begin + entry.size(), entry.filename())
+            })
+            .collect()
+    }
+
+    /// Returns the content of the regular file at the given path. Unlike
+    /// [`Self::entries`], this follows symlinks and hardlinks within the
+    /// archive until a regular file is found.
+    ///
+    /// Names and link targets in PAX extended headers and GNU long names are
+    /// respected. Absolute symlink targets are relative to the root of the
+    /// archive. Only the final path component is resolved, i.e., symlinks to
+    /// directories in the middle of `name` are not followed. Returns `None`
+    /// if there is no such file, if a link target doesn't exist in the
+    /// archive, or if more than [`MAX_LINK_DEPTH`] links had to be followed,
+    /// which is the case for link cycles.
+    #[must_use]
+    pub fn read_file(&self, name: &str) -> Option<Cow<'_, [u8]>> {
+        let mut path = normalize_path(name);
+        for _ in 0..=MAX_LINK_DEPTH {
+            let mut entries = ArchiveHeaderIterator::new(self.data.as_ref()).logical_entries();
+            let entry = entries.find(|entry| {
+                let header_name = filename_from_header(entry.header());
+                let name = entry.long_name().map_or_else(
+                    || header_name.as_str().ok(),
+                    |name| core::str::from_utf8(name).ok(),
+                );
+                path_eq(name.unwrap_or_default(), &path)
+            })?;
+            let link_target = entry.long_link_target().map_or_else(
+                || entry.header().linkname.as_str().ok(),
+                |target| core::str::from_utf8(target).ok(),
+            );
+            path = match entry.typeflag().try_to_type_flag().ok()? {
+                // Absolute symlinks are relative to the root of the archive.
+                TypeFlag::SYMTYPE if link_target?.starts_with('/') => normalize_path(link_target?),
+                // Other symlinks are relative to the directory of the link.
+                TypeFlag::SYMTYPE => {
+                    let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+                    normalize_path(&alloc::format!("{dir}/{}", link_target?))
+                }
+                // Hardlinks reference a previously archived file by its name.
+                TypeFlag::LINK => normalize_path(link_target?),
+                typeflag if typeflag.is_file_like() => return Some(Cow::Borrowed(entry.data())),
+                _ => return None,
+            };
+        }
+        warn!("Too many levels of links while resolving '{name}'");
+        None
+    }
+}
+
+/// Normalizes a path inside the archive by removing empty and `.` components
+/// and by resolving `..` components. The result has no leading or trailing
+/// slash. `..` components can't escape the root of the archive.
+#[cfg(feature = "alloc")]
+fn normalize_path(path: &str) -> String {
+    let mut normalized = String::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                let parent_len = normalized.rfind('/').unwrap_or(0);
+                normalized.truncate(parent_len);
+            }
+            component => {
+                if !normalized.is_empty() {
+                    normalized.push('/');
+                }
+                normalized.push_str(component);
+            }
+        }
+    }
+    normalized
+}
+
+/// Compares two paths while ignoring empty and `.` components, e.g., a
+/// leading `./` or a trailing `/`.
+#[cfg(feature = "alloc")]
+fn path_eq(a: &str, b: &str) -> bool {
+    fn components(path: &str) -> impl Iterator<Item = &str> {
+        path.split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+    }
+    components(a).eq(components(b))
+}
+
+/// Matches a filename against a glob pattern with `*` and `?`. See
+/// [`TarArchiveRef::entries_matching`].
+fn glob_match(
+    pattern: &[u8],
+    filename: &TarFormatString<POSIX_1003_MAX_FILENAME_LEN>,
+    ignore_case: bool,
+) -> bool {
+    let name = &filename.as_bytes()[..filename.size()];
+    if pattern.is_empty() {
+        return false;
+    }
+    let byte_eq = |a: u8, b: u8| {
+        if ignore_case {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+
+    // Greedy matching that backtracks to the most recent `*`, which is
+    // linear in the length of the name for each `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut last_star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                last_star = Some((p, n));
+                p += 1;
+            }
+            Some(&b) if b == b'?' || byte_eq(b, name[n]) => {
+                p += 1;
+                n += 1;
+            }
+            _ => {
+                let Some((star_p, star_n)) = last_star else {
+                    return false;
+                };
+                // Let the `*` consume one more byte.
+                last_star = Some((star_p, star_n + 1));
+                p = star_p + 1;
+                n = star_n + 1;
+            }
+        }
+    }
+    pattern[p..].iter().all(|&b| b == b'*')
+}
+
+#[cfg(feature = "alloc")]
+impl From<Box<[u8]>> for TarArchive {
+    fn from(data: Box<[u8]>) -> Self {
+        Self::new(data).unwrap()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<TarArchive> for Box<[u8]> {
+    fn from(ar: TarArchive) -> Self {
+        ar.data
+    }
+}
+
+/// Like [`TarArchive`] but the data is reference-counted, so that the data
+/// of entries can outlive a borrow of the archive. See
+/// [`Self::entries_shared`]. Create it with [`TarArchive::into_shared`].
+///
+/// This is only available with the `alloc` feature of this crate and on
+/// targets with atomic pointers.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedTarArchive {
+    data: Arc<[u8]>,
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl SharedTarArchive {
+    /// Returns the data of the whole archive. This only increments the
+    /// reference count, so the data stays alive as long as the returned
+    /// [`Arc`], even after the archive is dropped.
+    #[must_use]
+    pub fn data_arc(&self) -> Arc<[u8]> {
+        Arc::clone(&self.data)
+    }
+
+    /// Iterates over the files of the archive. See [`TarArchiveRef::entries`].
+    #[must_use]
+    pub fn entries(&self) -> ArchiveEntryIterator<'_> {
+        ArchiveEntryIterator::new(ArchiveHeaderIterator::new(&self.data))
+    }
+
+    /// Like [`Self::entries`] but yields the filename and the data of each
+    /// file as owned values, so that they can be stored beyond a borrow of
+    /// the archive. See [`SharedEntryIterator`].
+    #[must_use]
+    pub fn entries_shared(&self) -> SharedEntryIterator<'_> {
+        SharedEntryIterator {
+            entries: self.entries(),
+            data: &self.data,
+        }
+    }
+}
+
+/// Data of an entry of a [`SharedTarArchive`] that shares ownership of the
+/// data of the archive. Dereferences to the data of the entry.
+///
+/// Each instance holds a reference count of the archive's data. Cloning and
+/// dropping it is cheap, but not free, as the count is updated atomically.
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+#[derive(Clone, PartialEq, Eq)]
+pub struct SharedEntryData {
+    archive_data: Arc<[u8]>,
+    range: Range<usize>,
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl SharedEntryData {
+    /// The byte range of the data within the archive.
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl Deref for SharedEntryData {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.archive_data[self.range.clone()]
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl AsRef<[u8]> for SharedEntryData {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl Debug for SharedEntryData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SharedEntryData")
+            .field("range", &self.range)
+            .field("data", &"<bytes>")
+            .finish()
+    }
+}
+
+/// Iterator over the files of a [`TarArchive`] that yields their filename and
+/// their data as [`SharedEntryData`]. See [`SharedTarArchive::entries_shared`].
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+#[derive(Debug)]
+pub struct SharedEntryIterator<'a> {
+    entries: ArchiveEntryIterator<'a>,
+    data: &'a Arc<[u8]>,
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl Iterator for SharedEntryIterator<'_> {
+    type Item = (
+        TarFormatString<POSIX_1003_MAX_FILENAME_LEN>,
+        SharedEntryData,
+    );
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        let begin = (entry.header_block_index + 1) * BLOCKSIZE;
+        let data = SharedEntryData {
+            archive_data: Arc::clone(self.data),
+            range: begin..begin + entry.size(),
+        };
+        Some((entry.filename(), data))
+    }
+}
+
+/// A file of an archive that owns a copy of its header and its data. See
+/// [`TarArchive::into_entries`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq)]
+pub struct OwnedArchiveEntry {
+    filename: String,
+    posix_header: PosixHeader,
+    data: Box<[u8]>,
+}
+
+#[cfg(feature = "alloc")]
+impl OwnedArchiveEntry {
+    /// The untruncated filename of the entry. See
+    /// [`ArchiveEntry::filename_string`].
+    #[must_use]
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// The header of the entry, for access to the raw metadata.
+    #[must_use]
+    pub const fn posix_header(&self) -> &PosixHeader {
+        &self.posix_header
+    }
+
+    /// UNIX file permissions of the entry.
+    pub fn mode(&self) -> Result<ModeFlags, ModeError> {
+        self.posix_header.mode.to_flags()
+    }
+
+    /// Data of the entry.
+    #[must_use]
+    pub const fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The total size of the entry in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the data of the entry without copying it.
+    #[must_use]
+    pub fn into_data(self) -> Box<[u8]> {
+        self.data
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<ArchiveEntry<'_>> for OwnedArchiveEntry {
+    fn from(entry: ArchiveEntry<'_>) -> Self {
+        Self {
+            filename: entry.filename_string(),
+            posix_header: *entry.posix_header,
+            data: entry.data.into(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Debug for OwnedArchiveEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OwnedArchiveEntry")
+            .field("filename", &self.filename())
+            .field("size", &self.size())
+            .field("data", &"<bytes>")
+            .finish()
+    }
+}
+
+/// Iterator over the files of a consumed [`TarArchive`]. See
+/// [`TarArchive::into_entries`].
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct OwnedEntryIterator {
+    archive: TarArchive,
+    state: ParserState,
+}
+
+#[cfg(feature = "alloc")]
+impl Iterator for OwnedEntryIterator {
+    type Item = OwnedArchiveEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The iterator can't borrow from the archive it owns, so it resumes
+        // a borrowing iterator at the position of the next header.
+        let mut entries = self.archive.restore(self.state);
+        let entry = entries.next();
+        self.state = entries.save();
+        entry.map(OwnedArchiveEntry::from)
+    }
+}
+
+/// Wrapper type around bytes, which represents a Tar archive. To iterate the
+/// entries, use [`TarArchiveRef::entries`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TarArchiveRef<'a> {
+    data: &'a [u8],
+    /// See [`Self::new_lenient`].
+    lenient: bool,
+    /// See [`Self::with_block_size`].
+    record_size: Option<usize>,
+}
+
+#[allow(unused)]
+impl<'a> TarArchiveRef<'a> {
+    /// Creates a new archive wrapper type. The provided byte array is
+    /// interpreted as bytes in Tar archive format.
+    ///
+    /// Returns an error, if the sanity checks report problems. Compressed
+    /// data, such as a `.tar.gz` file, is reported as
+    /// [`TarError::CompressedInput`].
+    pub fn new(data: &'a [u8]) -> Result<Self, TarError> {
+        Self::validate(data).map(|_| Self {
+            data,
+            lenient: false,
+            record_size: None,
+        })
+    }
+
+    /// Best-effort recovery constructor for truncated archives, such as
+    /// partial downloads. Unlike [`Self::new`], the archive may end anywhere,
+    /// even without terminating zero blocks. The iteration ends at the end of
+    /// the data and the last entry contains whatever bytes of its payload
+    /// survived. Such an entry is flagged by [`ArchiveEntry::is_truncated`].
+    ///
+    /// The data must contain at least one complete header block. Prefer
+    /// [`Self::new`], if the archive is known to be intact.
+    pub fn new_lenient(data: &'a [u8]) -> Result<Self, TarError> {
+        match Self::validate(data) {
+            Err(TarError::CorruptData(_)) if data.len() >= BLOCKSIZE => {}
+            result => result?,
+        }
+        Ok(Self {
+            data,
+            lenient: true,
+            record_size: None,
+        })
+    }
+
+    /// Like [`Self::new`] but additionally verifies the checksum of every
+    /// header, so that corrupt headers are reported as
+    /// [`TarError::InvalidChecksum`] instead of producing garbage. This reads
+    /// every header once, so [`Self::new`] is faster.
+    pub fn new_validated(data: &'a [u8]) -> Result<Self, TarError> {
+        let archive = Self::new(data)?;
+        for (block_index, hdr) in ArchiveHeaderIterator::new(data) {
+            hdr.verify_checksum()
+                .map_err(|error| TarError::InvalidChecksum { block_index, error })?;
+        }
+        Ok(archive)
+    }
+
+    /// Checks the whole archive upfront, so that a corrupt archive, e.g., a
+    /// bad boot module, fails fast instead of during the iteration. Unlike
+    /// [`Self::new_validated`], this verifies the structure as well:
+    ///
+    /// - the checksum of every header is valid,
+    /// - every typeflag is known, and
+    /// - every payload fits into the archive, which also covers the
+    ///   terminating zero blocks.
+    ///
+    /// This reads every header once.
+    pub fn validate_full(&self) -> Result<(), FullValidationError> {
+        let mut headers = self.headers();
+        loop {
+            let block_index = headers.next_hdr_block_index;
+            let fail = |error| Err(FullValidationError { block_index, error });
+            let hdr = match headers.try_next() {
+                None => return Ok(()),
+                Some(Ok((_, hdr))) => hdr,
+                Some(Err(error)) => return fail(error),
+            };
+            if let Err(error) = hdr.verify_checksum() {
+                return fail(TarError::InvalidChecksum { block_index, error });
+            }
+            // GNU multi-volume continuations hold the data of a regular file.
+            if hdr.typeflag.try_to_type_flag().is_err() && hdr.typeflag.as_byte() != b'M' {
+                return fail(TarError::InvalidTypeFlag {
+                    block_index,
+                    typeflag: hdr.typeflag.as_byte(),
+                });
+            }
+            if hdr.typeflag.has_payload() {
+                match headers.payload(block_index, hdr) {
+                    Some(Ok(_)) => {}
+                    Some(Err(error)) => return fail(error),
+                    None => return fail(TarError::UnparsableSize { block_index }),
+                }
+            }
+        }
+    }
+
+    /// Like [`Self::new`] but for archives written with a custom record size,
+    /// such as archives of historical tape drives. GNU Tar, for example, uses
+    /// records of 20 blocks (10 KiB) by default.
+    ///
+    /// The record size must be a multiple of 512 bytes and the length of the
+    /// data must be a multiple of the record size. Each header is still 512
+    /// bytes and payloads are still padded to 512 bytes; only the padding of
+    /// the archive as a whole changes.
+    ///
+    /// The rest of the record after the terminating zero blocks must be zero
+    /// padding. Unlike for [`Self::new`], [`Self::trailing_data`] starts
+    /// exactly at the end of this record, instead of after all following
+    /// zero blocks.
+    pub fn with_block_size(data: &'a [u8], block_size: usize) -> Result<Self, TarError> {
+        let mut archive = Self::new(data)?;
+        let is_valid_block_size = block_size != 0 && block_size % BLOCKSIZE == 0;
+        if !is_valid_block_size || data.len() % block_size != 0 {
+            return Err(CorruptDataError.into());
+        }
+        archive.record_size = Some(block_size);
+        if let Some(terminator_offset) = archive.terminator_offset() {
+            let padding = &data[terminator_offset..archive.padding_end(terminator_offset)];
+            if padding.iter().any(|x| *x != 0) {
+                warn!("The record with the terminator isn't padded with zeroes");
+                return Err(CorruptDataError.into());
+            }
+        }
+        Ok(archive)
+    }
+
+    /// The record size that was passed to [`Self::with_block_size`], if any.
+    #[must_use]
+    pub const fn record_size(&self) -> Option<usize> {
+        self.record_size
+    }
+
+    /// Last-resort recovery constructor for mangled archives that start with
+    /// junk bytes, such as a UTF-8 BOM prepended by some tool.
+    ///
+    /// This is a heuristic: it scans every byte offset of the first
+    /// [`MAX_JUNK_BLOCKS`] blocks for a non-empty header with a valid checksum
+    /// and treats all preceding bytes as junk. Incomplete trailing blocks are
+    /// ignored. As the checksum is computed at each offset, this may be slow.
+    /// Prefer [`Self::new`], if the archive is known to be intact.
+    pub fn new_skip_junk(data: &'a [u8]) -> Result<Self, TarError> {
+        let scan_len = data.len().min(MAX_JUNK_BLOCKS * BLOCKSIZE);
+        let offset = (0..scan_len).find(|&offset| {
+            data[offset..].block(0).is_some_and(|block| {
+                let hdr = block.as_header();
+                !hdr.is_zero_block() && hdr.checksum_is_valid()
+            })
+        });
+        let Some(offset) = offset else {
+            warn!("No valid header found in the first {scan_len} bytes");
+            Self::validate(data)?;
+            return Err(CorruptDataError.into());
+        };
+        if offset != 0 {
+            warn!("Skipping {offset} bytes of junk before the archive");
+        }
+        let data = &data[offset..];
+        Self::new(&data[..data.len() - data.len() % BLOCKSIZE])
+    }
+
+    /// Iterates the headers, which respects [`Self::new_lenient`].
+    fn headers(&self) -> ArchiveHeaderIterator<'a> {
+        if self.lenient {
+            ArchiveHeaderIterator::new_lenient(self.data)
+        } else {
+            ArchiveHeaderIterator::new(self.data)
+        }
+    }
+
+    fn validate(data: &'a [u8]) -> Result<(), TarError> {
+        // A valid header may start with the same bytes as a compressed
+        // stream, but a compressed stream practically never has a valid
+        // header checksum.
+        if let Some(kind) = Compression::detect(data) {
+            let is_valid_header = data[..]
+                .block(0)
+                .is_some_and(|block| block.as_header().checksum_is_valid());
+            if !is_valid_header {
+                return Err(TarError::CompressedInput { kind });
+            }
+        }
+
+        let is_malformed = (data.len() % BLOCKSIZE) != 0;
+        let has_min_block_count = data.len() / BLOCKSIZE >= MIN_BLOCK_COUNT;
+        (!data.is_empty() && !is_malformed && has_min_block_count)
+            .then_some(())
+            .ok_or(TarError::CorruptData(CorruptDataError))
+    }
+
+    /// Creates an [`ArchiveEntryIterator`].
+    #[must_use]
+    pub fn entries(&self) -> ArchiveEntryIterator<'_> {
+        ArchiveEntryIterator::new(self.headers())
+    }
+
+    /// Returns the number of entries that [`Self::entries`] yields. This
+    /// walks all headers of the archive once.
+    #[must_use]
+    pub fn entry_count(&self) -> usize {
+        self.entries().count()
+    }
+
+    /// Returns `true`, if [`Self::entries`] yields no entries, e.g., because
+    /// the archive only contains directories.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries().next().is_none()
+    }
+
+    /// Resumes an iteration at a position that was saved with
+    /// [`ArchiveEntryIterator::save`]. See [`ParserState`].
+    #[must_use]
+    pub fn restore(&self, state: ParserState) -> ArchiveEntryIterator<'_> {
+        ArchiveEntryIterator::with_state(self.headers(), state)
+    }
+
+    /// Resumes an iteration at the header at the given block index, e.g.,
+    /// one that was returned by [`ArchiveEntryIterator::current_block`].
+    /// Returns `None`, if the block index is beyond the archive.
+    ///
+    /// Unlike [`Self::restore`], this doesn't restore a pending PAX extended
+    /// header, so prefer [`ParserState`] for archives that use them.
+    #[must_use]
+    pub fn entries_from_block(&self, block_index: usize) -> Option<ArchiveEntryIterator<'_>> {
+        (block_index < self.data.len() / BLOCKSIZE).then(|| {
+            let state = ParserState {
+                next_hdr_block_index: block_index,
+                ..ParserState::new()
+            };
+            ArchiveEntryIterator::with_state(self.headers(), state)
+        })
+    }
+
+    /// Like [`Self::entries`] but each entry is paired with the PAX extended
+    /// header records that apply to it, if there are any. This includes the
+    /// records of the most recent global extended header.
+    #[must_use]
+    pub fn entries_with_pax(&self) -> PaxEntryIterator<'_> {
+        PaxEntryIterator(self.entries())
+    }
+
+    /// Iterates over the symlinks and hardlinks of the archive, which are
+    /// skipped by [`Self::entries`]. Use [`ArchiveEntry::link_target`] to get
+    /// their targets.
+    #[must_use]
+    pub fn links(&self) -> LinkIterator<'_> {
+        LinkIterator(self.headers().logical_entries())
+    }
+
+    /// Iterates over all entries of the archive, independent of their type,
+    /// including directories, links, FIFOs, and devices. Unlike
+    /// [`Self::entries`], this neither applies PAX extended headers nor GNU
+    /// long names, which are yielded as entries themselves. See [`RawEntry`].
+    #[must_use]
+    pub fn raw_entries(&self) -> RawEntryIterator<'_> {
+        RawEntryIterator(self.headers())
+    }
+
+    /// Like [`Self::entries`] but yields an error instead of silently ending
+    /// the iteration, if the archive is malformed, e.g., if the payload of an
+    /// entry doesn't fit into the archive or if a typeflag is invalid. This
+    /// distinguishes a clean end of the archive from a corrupt one. The
+    /// iteration ends after an error.
+    #[must_use]
+    pub fn try_entries(&self) -> TryEntryIterator<'_> {
+        TryEntryIterator(self.entries())
+    }
+
+    /// Like [`Self::entries`] but stops with [`StopReason::SizeLimitReached`],
+    /// once the cumulative size of the yielded entries would exceed
+    /// `max_total` bytes. See [`SizeLimitedEntryIterator`].
+    #[must_use]
+    pub fn entries_size_limited(&self, max_total: u64) -> SizeLimitedEntryIterator<'_> {
+        SizeLimitedEntryIterator::new(self.entries(), max_total)
+    }
+
+    /// Like [`Self::entries`] but calls `should_continue` before each entry
+    /// and stops with [`StopReason::Cancelled`], once it returns `false`.
+    /// See [`CancellableEntryIterator`].
+    pub fn entries_until<F: FnMut() -> bool>(
+        &self,
+        should_continue: F,
+    ) -> CancellableEntryIterator<'_, F> {
+        CancellableEntryIterator::new(self.entries(), should_continue)
+    }
+
+    /// SHA-256 digest of the whole archive, e.g., to compare it against a
+    /// published checksum.
+    ///
+    /// This is only available with the `sha2` feature of this crate.
+    #[cfg(feature = "sha2")]
+    #[must_use]
+    pub fn sha256(&self) -> [u8; 32] {
+        sha256(self.data)
+    }
+
+    /// Iterates over the regular files whose filename matches the glob
+    /// `pattern`. A `*` matches any sequence of bytes, including `/`, and a
+    /// `?` matches exactly one byte. All other bytes match themselves. An
+    /// empty pattern matches nothing.
+    ///
+    /// For example, `*.conf` matches `etc/app.conf`, as Tar paths are flat
+    /// strings.
+    pub fn entries_matching<'p>(
+        &self,
+        pattern: &'p str,
+    ) -> impl Iterator<Item = ArchiveEntry<'a>> + 'p
+    where
+        'a: 'p,
+    {
+        ArchiveEntryIterator::new(self.headers())
+            .filter(move |entry| glob_match(pattern.as_bytes(), &entry.filename, false))
+    }
+
+    /// Like [`Self::entries_matching`] but ignores the ASCII case, e.g.,
+    /// `*.TXT` matches `readme.txt`.
+    pub fn entries_matching_ignore_ascii_case<'p>(
+        &self,
+        pattern: &'p str,
+    ) -> impl Iterator<Item = ArchiveEntry<'a>> + 'p
+    where
+        'a: 'p,
+    {
+        ArchiveEntryIterator::new(self.headers())
+            .filter(move |entry| glob_match(pattern.as_bytes(), &entry.filename, true))
+    }
+
+    /// Iterates over the data of all regular files in the order of the
+    /// archive, e.g., to compute a checksum of the content of the archive
+    /// that ignores all metadata.
+    pub fn payload_bytes(&self) -> impl Iterator<Item = &'a [u8]> {
+        ArchiveEntryIterator::new(self.headers()).map(|entry| entry.data())
+    }
+
+    /// Returns the data of the first regular file with the given name. The
+    /// search stops at the first match, so files at the beginning of the
+    /// archive are found fast. The name is compared like the filenames of
+    /// [`Self::entries`], i.e., including the ustar prefix.
+    #[must_use]
+    pub fn read(&self, name: &str) -> Option<&'a [u8]> {
+        self.find(name).map(|entry| entry.data())
+    }
+
+    /// Returns the first regular file whose filename equals `path` byte by
+    /// byte. The filename includes the ustar prefix, as in [`Self::entries`].
+    ///
+    /// Tar has no index, so this is `O(n)` in the number of entries. The
+    /// search stops at the first match.
+    #[must_use]
+    pub fn find(&self, path: &str) -> Option<ArchiveEntry<'a>> {
+        self.find_by(|entry| entry.filename().as_str() == Ok(path))
+    }
+
+    /// Like [`Self::find`] but returns the first regular file that matches
+    /// the predicate.
+    pub fn find_by<F: FnMut(&ArchiveEntry<'a>) -> bool>(
+        &self,
+        predicate: F,
+    ) -> Option<ArchiveEntry<'a>> {
+        ArchiveEntryIterator::new(self.headers()).find(predicate)
+    }
+
+    /// Like [`Self::read`] but looks up several files in a single pass over
+    /// the archive. The data is returned in the order of `names`. The search
+    /// stops, once all files are found. See also [`extract_named!`].
+    #[must_use]
+    pub fn read_many<const N: usize>(&self, names: [&str; N]) -> [Option<&'a [u8]>; N] {
+        let mut found = [None; N];
+        let mut missing = N;
+        for entry in ArchiveEntryIterator::new(self.headers()) {
+            if missing == 0 {
+                break;
+            }
+            let filename = entry.filename();
+            for (name, found) in names.iter().zip(found.iter_mut()) {
+                if found.is_none() && filename.as_str() == Ok(*name) {
+                    *found = Some(entry.data());
+                    missing -= 1;
+                }
+            }
+        }
+        found
+    }
+
+    /// Whether the archive contains a regular file for each of the given
+    /// names, e.g., to check at boot time that a ramdisk has everything that
+    /// is needed. See [`Self::missing_names`].
+    #[must_use]
+    pub fn contains_all(&self, names: &[&str]) -> bool {
+        self.missing_names(names).next().is_none()
+    }
+
+    /// Returns the names for which the archive contains no regular file. The
+    /// names are compared like in [`Self::read`].
+    ///
+    /// The archive is scanned once per 64 names, so that no heap is needed.
+    /// A scan ends early, once all of its names are found.
+    pub fn missing_names<'b, 'n>(
+        &'b self,
+        names: &'b [&'n str],
+    ) -> impl Iterator<Item = &'n str> + 'b {
+        names.chunks(u64::BITS as usize).flat_map(move |names| {
+            let found = self.find_names(names);
+            names
+                .iter()
+                .enumerate()
+                .filter(move |(index, _)| found & (1 << index) == 0)
+                .map(|(_, name)| *name)
+        })
+    }
+
+    /// Returns a bitset of the given names, at most 64, for which the archive
+    /// contains a regular file.
+    fn find_names(&self, names: &[&str]) -> u64 {
+        debug_assert!(names.len() <= u64::BITS as usize);
+        let all = u64::MAX >> (u64::BITS as usize - names.len());
+        let mut found = 0_u64;
+        for entry in ArchiveEntryIterator::new(self.headers()) {
+            if found == all {
+                break;
+            }
+            let filename = entry.filename();
+            for (index, name) in names.iter().enumerate() {
+                if filename.as_str() == Ok(*name) {
+                    found |= 1 << index;
+                }
+            }
+        }
+        found
+    }
+
+    /// Writes a table of contents of the files of the archive into the
+    /// provided slice and returns the number of written entries. This enables
+    /// random access to the entries via [`Self::entry_at`] without a heap.
+    ///
+    /// If the slice is too small, only the first `out.len()` entries are
+    /// written. Use `self.entries().count()` to find the required length.
+    pub fn toc_into(&self, out: &mut [TocEntry]) -> usize {
+        let mut entries = self.entries();
+        out.iter_mut()
+            .map_while(|toc_entry| {
+                let state = entries.save();
+                let entry = entries.next()?;
+                *toc_entry = TocEntry {
+                    header_offset: entry.header_block_index * BLOCKSIZE,
+                    size: entry.size(),
+                    state,
+                };
+                Some(())
+            })
+            .count()
+    }
+
+    /// Reconstructs the entry at the location written by [`Self::toc_into`].
+    ///
+    /// Returns `None`, if the location doesn't point to a regular file of
+    /// this archive.
+    #[must_use]
+    pub fn entry_at(&self, toc_entry: &TocEntry) -> Option<ArchiveEntry<'a>> {
+        // Only the extension headers and skipped entries directly before the
+        // entry are parsed again.
+        let entry = ArchiveEntryIterator::with_state(self.headers(), toc_entry.state).next()?;
+        let is_same_entry = entry.header_block_index * BLOCKSIZE == toc_entry.header_offset
+            && entry.size() == toc_entry.size;
+        is_same_entry.then_some(entry)
+    }
+
+    /// Checks whether the names of the files are sorted and unique, e.g., to
+    /// verify reproducible builds. Names are compared byte-wise.
+    ///
+    /// This is a single pass that only compares consecutive names, so
+    /// duplicates are only found if they are adjacent. In sorted archives,
+    /// this finds all duplicates.
+    #[must_use]
+    pub fn check_names(&self) -> NameCheck {
+        let mut check = NameCheck {
+            has_duplicates: false,
+            is_sorted: true,
+        };
+        let mut previous: Option<TarFormatString<POSIX_1003_MAX_FILENAME_LEN>> = None;
+        for entry in self.entries() {
+            let name = entry.filename();
+            if let Some(previous) = previous {
+                let previous = &previous.as_bytes()[..previous.size()];
+                let current = &name.as_bytes()[..name.size()];
+                check.has_duplicates |= previous == current;
+                check.is_sorted &= previous <= current;
+            }
+            previous = Some(name);
+        }
+        check
+    }
+
+    /// Counts the headers of the archive per type in a single pass, which
+    /// helps to characterize unfamiliar archives. Unlike [`Self::entries`],
+    /// this also counts directories, links, and extended headers.
+    #[must_use]
+    pub fn inspect(&self) -> ArchiveStats {
+        let mut stats = ArchiveStats::default();
+        for (_, hdr) in self.headers() {
+            match hdr.typeflag.try_to_type_flag() {
+                Ok(typeflag) => stats.counts[typeflag.index()] += 1,
+                Err(_) => stats.invalid_count += 1,
+            }
+        }
+        stats
+    }
+
+    /// Sum of the sizes of all regular files in bytes, e.g., to allocate the
+    /// memory for an extraction at once. This only parses the headers and
+    /// honors the `size` records of PAX extended headers. The sum saturates
+    /// at [`u64::MAX`].
+    ///
+    /// Returns the error that [`Self::try_entries`] reports, e.g., if a size
+    /// field is unparsable.
+    pub fn total_payload_bytes(&self) -> Result<u64, TarError> {
+        self.try_entries().try_fold(0_u64, |total, entry| {
+            Ok(total.saturating_add(entry?.size() as u64))
+        })
+    }
+
+    /// Byte offset of the first terminating zero block, i.e., the end of the
+    /// last entry. Returns `None`, if no terminator is found, e.g., because
+    /// the archive is corrupt.
+    #[must_use]
+    pub fn terminator_offset(&self) -> Option<usize> {
+        let mut headers = self.headers();
+        loop {
+            let block_index = headers.next_hdr_block_index;
+            if headers.is_zero_block(block_index) {
+                return Some(block_index * BLOCKSIZE);
+            }
+            headers.next()?;
+        }
+    }
+
+    /// Size of the actual content of the archive in bytes, i.e., of all
+    /// headers and payloads. Unlike the length of the data, this excludes
+    /// the terminating zero blocks and any padding after them. This is
+    /// useful to trim an over-padded archive.
+    ///
+    /// Returns the full length, if no terminator is found.
+    #[must_use]
+    pub fn content_size(&self) -> usize {
+        self.terminator_offset().unwrap_or(self.data.len())
+    }
+
+    /// Returns the data after the two terminating zero blocks and after the
+    /// zero blocks that pad the archive to the record size, such as a
+    /// detached signature that a tool appended to the archive. Returns an
+    /// empty slice, if there is no such data or no terminator.
+    ///
+    /// If the record size is unknown, all zero blocks after the terminator
+    /// are considered as padding. See [`Self::with_block_size`].
+    #[must_use]
+    pub fn trailing_data(&self) -> &'a [u8] {
+        let Some(terminator_offset) = self.terminator_offset() else {
+            return &[];
+        };
+        &self.data[self.padding_end(terminator_offset)..]
+    }
+
+    /// Offset of the end of the padding after the terminator at the given
+    /// offset.
+    fn padding_end(&self, terminator_offset: usize) -> usize {
+        let padding_offset = (terminator_offset + 2 * BLOCKSIZE).min(self.data.len());
+        if let Some(record_size) = self.record_size {
+            return padding_offset
+                .next_multiple_of(record_size)
+                .min(self.data.len());
+        }
+        let padding_len = self.data[padding_offset..]
+            .chunks(BLOCKSIZE)
+            .take_while(|block| block.iter().all(|x| *x == 0))
+            .map(<[u8]>::len)
+            .sum::<usize>();
+        padding_offset + padding_len
+    }
+
+    /// Splits concatenated archives, e.g., from `cat a.tar b.tar`, into their
+    /// members. The iteration of the entries stops at the first terminator,
+    /// so use this to read all of them:
+    ///
+    /// ```rust
+    /// # use tar_no_std::TarArchiveRef;
+    /// # let data = include_bytes!("../tests/gnu_tar_default.tar");
+    /// let archive = TarArchiveRef::new(data).unwrap();
+    /// for member in archive.members() {
+    ///     for entry in member.entries() {
+    ///         println!("{entry}");
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// The first member is this archive. Each further member starts at the
+    /// [`Self::trailing_data`] of the previous one, if it begins with a
+    /// header with a valid checksum. Other trailing data, such as a detached
+    /// signature, ends the iteration. The members inherit the leniency of
+    /// [`Self::new_lenient`].
+    #[must_use]
+    pub fn members(&self) -> MemberIterator<'a> {
+        MemberIterator {
+            next: Some(self.clone()),
+        }
+    }
+
+    /// Reconstructs an equivalent archive from the parsed entries with the
+    /// [`TarBuilder`]. Diffing the input and the output reveals what this
+    /// crate doesn't preserve, which is useful to verify read and write
+    /// fidelity or to normalize archives.
+    ///
+    /// Preserved exactly are the untruncated filename, the data, the
+    /// permissions, the numeric owner and group, the modification time, and
+    /// the owner and group names. The `uid`, `gid`, and `mtime` records of PAX
+    /// extended headers take precedence over the header, see
+    /// [`ArchiveEntry::uid`]. Normalized are the format, the checksum,
+    /// the padding, and the terminating zero blocks. The format is ustar,
+    /// unless a name or a number doesn't fit into the ustar header, in which
+    /// case the GNU format with long name records and base-256 numbers is
+    /// used. Entries that [`Self::entries`]
+    /// doesn't yield, such as directories, links, and extended headers, are
+    /// dropped.
+    ///
+    /// Names that aren't valid UTF-8 are reported as
+    /// [`BuildError::InvalidName`].
+    ///
+    /// This is only available with the `alloc` feature of this crate.
+    #[cfg(feature = "alloc")]
+    pub fn rebuild(&self) -> Result<Vec<u8>, BuildError> {
+        match self.rebuild_with_format(TarFormat::Ustar) {
+            Err(BuildError::NameTooLong | BuildError::NumberTooLarge) => {
+                self.rebuild_with_format(TarFormat::Gnu)
+            }
+            result => result,
+        }
+    }
+
+    /// See [`Self::rebuild`].
+    #[cfg(feature = "alloc")]
+    fn rebuild_with_format(&self, format: TarFormat) -> Result<Vec<u8>, BuildError> {
+        let mut builder = TarBuilder::with_format(format);
+        for entry in self.entries() {
+            let hdr = entry.posix_header();
+            let defaults = EntryOptions::default();
+            let options = EntryOptions {
+                mode: hdr.mode.to_flags().unwrap_or(defaults.mode),
+                uid: entry.uid().unwrap_or(defaults.uid),
+                gid: entry.gid().unwrap_or(defaults.gid),
+                mtime: entry.modification_time().unwrap_or(defaults.mtime),
+                uname: hdr.uname.as_str().unwrap_or(defaults.uname),
+                gname: hdr.gname.as_str().unwrap_or(defaults.gname),
+            };
+            let name =
+                core::str::from_utf8(entry.full_name()).map_err(|_| BuildError::InvalidName)?;
+            builder.append_file_with(name, &options, entry.data())?;
+        }
+        Ok(builder.finish().into_vec())
+    }
+
+    /// The flavor of the archive according to its first header. See
+    /// [`PosixHeader::detect_format`].
+    ///
+    /// Archives may mix formats, e.g., a PAX archive only has extended headers
+    /// for the files that need them. Use [`Self::uses_extensions`] for a scan
+    /// of all headers.
+    #[must_use]
+    pub fn format(&self) -> ArchiveFormat {
+        self.headers()
+            .next()
+            .map_or(ArchiveFormat::Unknown, |(_, hdr)| hdr.detect_format())
+    }
+
+    /// Scans the headers of the archive for extensions of the Tar format that
+    /// this crate can't fully handle. Callers can use this to decide whether
+    /// to fall back to a more complete Tar implementation.
+    ///
+    /// This only looks at the headers and not at the payload.
+    #[must_use]
+    pub fn uses_extensions(&self) -> ExtensionFlags {
+        self.headers()
+            .map(|(_, hdr)| hdr)
+            .fold(ExtensionFlags::empty(), |flags, hdr| {
+                let typeflag = match hdr.typeflag.as_byte() {
+                    b'x' => ExtensionFlags::PaxExtendedHeader,
+                    b'g' => ExtensionFlags::PaxGlobalHeader,
+                    b'L' => ExtensionFlags::GnuLongName,
+                    b'K' => ExtensionFlags::GnuLongLink,
+                    b'S' => ExtensionFlags::GnuSparse,
+                    _ => ExtensionFlags::empty(),
+                };
+                let magic = if hdr.version_kind() == VersionKind::Gnu {
+                    ExtensionFlags::GnuMagic
+                } else {
+                    ExtensionFlags::empty()
+                };
+                flags | typeflag | magic
+            })
+    }
+
+    /// Classifies each block of the archive as header, data, zero block, or
+    /// unknown block in a single forward pass. This visualizes the structure
+    /// of an archive and helps to diagnose why it fails to parse. See
+    /// [`BlockClass`].
+    #[must_use]
+    pub const fn classified_blocks(&self) -> ClassifiedBlockIterator<'a> {
+        ClassifiedBlockIterator {
+            data: self.data,
+            block_index: 0,
+            data_blocks_left: 0,
+            is_terminated: false,
+        }
+    }
+}
+
+/// Kind of a block of an archive. See [`TarArchiveRef::classified_blocks`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlockClass {
+    /// A header with a valid checksum.
+    Header,
+    /// A block of the payload of the preceding header.
+    Data,
+    /// A zero block outside of a payload, such as the terminator.
+    Zero,
+    /// A block where a header is expected but whose checksum is invalid, or
+    /// a non-zero block after the terminator.
+    Unknown,
+}
+
+/// Iterator over the [`BlockClass`] of each block of an archive. See
+/// [`TarArchiveRef::classified_blocks`].
+#[derive(Clone, Debug)]
+pub struct ClassifiedBlockIterator<'a> {
+    data: &'a [u8],
+    block_index: usize,
+    /// Number of payload blocks of the current header that are left.
+    data_blocks_left: u64,
+    /// Whether a zero block outside of a payload was found.
+    is_terminated: bool,
+}
+
+impl Iterator for ClassifiedBlockIterator<'_> {
+    type Item = BlockClass;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = self.data.block(self.block_index)?;
+        self.block_index += 1;
+        let hdr = block.as_header();
+        let class = if self.data_blocks_left > 0 {
+            self.data_blocks_left -= 1;
+            BlockClass::Data
+        } else if hdr.is_zero_block() {
+            self.is_terminated = true;
+            BlockClass::Zero
+        } else if self.is_terminated || !hdr.checksum_is_valid() {
+            BlockClass::Unknown
+        } else {
+            if hdr.typeflag.has_payload() {
+                // Following blocks are classified as unknown, if the size is
+                // unparsable, unless they happen to be valid headers.
+                let size = hdr.size.as_number::<u64>().unwrap_or(0);
+                self.data_blocks_left = blocks_for_payload(size);
+            }
+            BlockClass::Header
+        };
+        Some(class)
+    }
+}
+
+bitflags::bitflags! {
+    /// Extensions of the Tar format found by [`TarArchiveRef::uses_extensions`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ExtensionFlags: u8 {
+        /// PAX extended header for the next entry (typeflag `x`). Its records
+        /// may override fields of the header. This crate applies `path`,
+        /// `linkpath`, `size`, `uid`, `gid`, and `mtime`, but ignores the
+        /// others, such as `uname`, `gname`, and `atime`.
+        const PaxExtendedHeader = 1 << 0;
+        /// PAX global header (typeflag `g`).
+        const PaxGlobalHeader = 1 << 1;
+        /// GNU long name of the next entry (typeflag `L`).
+        const GnuLongName = 1 << 2;
+        /// GNU long link name of the next entry (typeflag `K`).
+        const GnuLongLink = 1 << 3;
+        /// GNU sparse file (typeflag `S`).
+        const GnuSparse = 1 << 4;
+        /// Header with the magic of the GNU or old GNU format (`ustar `),
+        /// which may store additional data in the `prefix` field.
+        const GnuMagic = 1 << 5;
+    }
+}
+
+/// Iterator over the members of concatenated archives. See
+/// [`TarArchiveRef::members`].
+#[derive(Clone, Debug)]
+pub struct MemberIterator<'a> {
+    next: Option<TarArchiveRef<'a>>,
+}
+
+impl<'a> Iterator for MemberIterator<'a> {
+    type Item = TarArchiveRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let archive = self.next.take()?;
+        let trailing_data = archive.trailing_data();
+        let starts_with_header = trailing_data
+            .block(0)
+            .is_some_and(|block| block.as_header().checksum_is_valid());
+        if starts_with_header {
+            let next = if archive.lenient {
+                TarArchiveRef::new_lenient(trailing_data)
+            } else {
+                TarArchiveRef::new(trailing_data)
+            };
+            self.next = next
+                .inspect_err(|e| warn!("Can't read the next member of the archive: {e}"))
+                .ok();
+        }
+        if let Some(next) = &mut self.next {
+            next.record_size = archive.record_size;
+        }
+        let member_len = archive.data.len() - trailing_data.len();
+        Some(TarArchiveRef {
+            data: &archive.data[..member_len],
+            ..archive
+        })
+    }
+}
+
+#[cfg(feature = "sha2")]
+fn sha256(data: &[u8]) -> [u8; 32] {
+    use sha2::Digest;
+    sha2::Sha256::digest(data).into()
+}
+
+/// Iterates over the headers of the Tar archive.
+#[derive(Clone, Debug)]
+pub struct ArchiveHeaderIterator<'a> {
+    archive_data: &'a [u8],
+    next_hdr_block_index: usize,
+    /// Whether a truncated archive yields its partial data instead of
+    /// errors. See [`TarArchiveRef::new_lenient`].
+    lenient: bool,
+}
+
+impl<'a> ArchiveHeaderIterator<'a> {
+    #[must_use]
+    pub fn new(archive: &'a [u8]) -> Self {
+        assert!(!archive.is_empty());
+        assert_eq!(archive.len() % BLOCKSIZE, 0);
+        Self {
+            archive_data: archive,
+            next_hdr_block_index: 0,
+            lenient: false,
+        }
+    }
+
+    /// Like [`Self::new`] but for the best-effort recovery of truncated
+    /// archives. The archive may end anywhere, even within a block.
+    fn new_lenient(archive: &'a [u8]) -> Self {
+        assert!(archive.len() >= BLOCKSIZE);
+        Self {
+            archive_data: archive,
+            next_hdr_block_index: 0,
+            lenient: true,
+        }
+    }
+
+    /// Folds the PAX extended headers and the GNU long name and long link
+    /// records into the header that they belong to. See [`LogicalEntry`].
+    #[must_use]
+    pub const fn logical_entries(self) -> LogicalEntryIterator<'a> {
+        LogicalEntryIterator::new(self)
+    }
+
+    /// Parse the memory at the given block as [`PosixHeader`].
+    fn block_as_header(&self, block_index: usize) -> &'a PosixHeader {
+        unsafe {
+            self.archive_data
+                .as_ptr()
+                .add(block_index * BLOCKSIZE)
+                .cast::<PosixHeader>()
+                .as_ref()
+                .unwrap()
+        }
+    }
+
+    /// Whether the given block exists and only consists of zeroes.
+    fn is_zero_block(&self, block_index: BlockIndex) -> bool {
+        block_index < self.archive_data.len() / BLOCKSIZE
+            && self.block_as_header(block_index).is_zero_block()
+    }
+
+    /// Returns the blocks from the first given index up to and including the
+    /// header block at the given index and the data blocks of a payload of
+    /// the given length, including the padding.
+    fn raw_blocks(
+        &self,
+        first_block_index: BlockIndex,
+        block_index: BlockIndex,
+        payload_len: usize,
+    ) -> &'a [u8] {
+        let begin = first_block_index * BLOCKSIZE;
+        let end = (block_index + 1) * BLOCKSIZE + payload_len.next_multiple_of(BLOCKSIZE);
+        // A truncated archive in lenient mode ends within the payload.
+        &self.archive_data[begin..end.min(self.archive_data.len())]
+    }
+
+    /// Returns the payload that belongs to the header at the given block
+    /// index. Returns `None`, if the size can't be parsed, and
+    /// [`TarError::PayloadTooLarge`], if the payload doesn't fit into the
+    /// archive.
+    fn payload(
+        &self,
+        block_index: BlockIndex,
+        hdr: &PosixHeader,
+    ) -> Option<Result<&'a [u8], TarError>> {
+        // Parse as u64, so that sizes beyond usize::MAX on 32-bit platforms
+        // are reported instead of being unparsable.
+        let payload_size: u64 = hdr
+            .size
+            .as_number()
+            .inspect_err(|e| {
+                log_header!(
+                    error,
+                    block_index,
+                    hdr,
+                    "Can't parse the file size {:?} from the header. {e:#?}",
+                    hdr.size
+                );
+            })
+            .ok()?;
+        Some(self.payload_of_size(block_index, hdr, payload_size))
+    }
+
+    /// Like [`Self::payload`] but with the given size instead of the size
+    /// field of the header, e.g., from a PAX extended header.
+    fn payload_of_size(
+        &self,
+        block_index: BlockIndex,
+        hdr: &PosixHeader,
+        payload_size: u64,
+    ) -> Result<&'a [u8], TarError> {
+        let idx_first_data_block = block_index + 1;
+        let idx_begin = idx_first_data_block * BLOCKSIZE;
+
+        if self.lenient {
+            // Return whatever survived of the payload.
+            let available = self.archive_data.len() - idx_begin;
+            let len = usize::try_from(payload_size).map_or(available, |size| size.min(available));
+            if (len as u64) < payload_size {
+                log_header!(
+                    warn,
+                    block_index,
+                    hdr,
+                    "The archive is truncated. Only {len} of {payload_size} bytes of the payload are present"
+                );
+            }
+            return Ok(&self.archive_data[idx_begin..idx_begin + len]);
+        }
+
+        // This doesn't subtract with overflow as we ensured a minimum size in
+        // the constructor.
+        let max_data_end_index_exclusive = self.archive_data.len() - 2 * BLOCKSIZE;
+        let idx_end_exclusive = usize::try_from(payload_size)
+            .ok()
+            .and_then(|payload_size| idx_begin.checked_add(payload_size))
+            .filter(|idx_end| *idx_end <= max_data_end_index_exclusive);
+        let Some(idx_end_exclusive) = idx_end_exclusive else {
+            log_header!(
+                warn,
+                block_index,
+                hdr,
+                "Invalid Tar. The size of the payload ({payload_size}) is larger than what is valid"
+            );
+            return Err(TarError::PayloadTooLarge { size: payload_size });
+        };
+
+        Ok(&self.archive_data[idx_begin..idx_end_exclusive])
+    }
+}
+
+/// Reconstructs the full filename of an entry. For ustar archives, this
+/// prepends the `prefix` to the `name`.
+fn filename_from_header(hdr: &PosixHeader) -> TarFormatString<POSIX_1003_MAX_FILENAME_LEN> {
+    let mut filename: TarFormatString<256> =
+        TarFormatString::<POSIX_1003_MAX_FILENAME_LEN>::new([0; POSIX_1003_MAX_FILENAME_LEN]);
+
+    // POXIS_1003 long filename check
+    // https://docs.scinet.utoronto.ca/index.php/(POSIX_1003.1_USTAR)
+    // The prefix, the separator, and the name always fit, but a malformed
+    // header must never crash the parser.
+    let mut append = || {
+        if hdr.uses_prefix() {
+            filename.try_append(&hdr.prefix)?;
+            filename.try_append(&TarFormatString::<1>::new([b'/']))?;
+        }
+        filename.try_append(&hdr.name)
+    };
+    if let Err(e) = append() {
+        warn!("Can't reconstruct the filename from the prefix and the name: {e}");
+    }
+    filename
+}
+
+type BlockIndex = usize;
+
+impl<'a> Iterator for ArchiveHeaderIterator<'a> {
+    type Item = (BlockIndex, &'a PosixHeader);
+
+    /// Returns the next header. Internally, it updates the necessary data
+    /// structures to not read the same header multiple times.
+    ///
+    /// This returns `None` if either no further headers are found, if a
+    /// header can't be parsed, or if the terminating zero blocks are reached.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next()?.ok()
+    }
+
+    /// Each header occupies at least one block, so the remaining blocks are
+    /// an upper bound for the remaining headers.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.is_zero_block(self.next_hdr_block_index) {
+            return (0, Some(0));
+        }
+        let total_block_count = self.archive_data.len() / BLOCKSIZE;
+        let remaining = total_block_count.saturating_sub(self.next_hdr_block_index);
+        (0, Some(remaining))
+    }
+}
+
+impl<'a> ArchiveHeaderIterator<'a> {
+    /// Like [`Iterator::next`] but reports why the iteration ended early.
+    /// The iteration ends after an error.
+    fn try_next(&mut self) -> Option<Result<(BlockIndex, &'a PosixHeader), TarError>> {
+        // Set after an error.
+        if self.next_hdr_block_index == usize::MAX {
+            return None;
+        }
+        let total_block_count = self.archive_data.len() / BLOCKSIZE;
+        if self.next_hdr_block_index >= total_block_count {
+            // A truncated archive just ends without terminator.
+            if self.lenient {
+                return None;
+            }
+            warn!("Invalid block index. Probably the Tar is corrupt: an header had an invalid payload size");
+            self.next_hdr_block_index = usize::MAX;
+            return Some(Err(TarError::MissingTerminator));
+        }
+        // The terminator ends the archive. Everything after it, e.g., padding
+        // to a blocking factor or leftover data, is ignored.
+        if self.is_zero_block(self.next_hdr_block_index) {
+            let next_block_index = self.next_hdr_block_index + 1;
+            if next_block_index < total_block_count && !self.is_zero_block(next_block_index) {
+                warn!("A lone zero block at block index {} ends the archive. Probably the Tar is corrupt", self.next_hdr_block_index);
+            }
+            return None;
+        }
+
+        let hdr = self.block_as_header(self.next_hdr_block_index);
+        let block_index = self.next_hdr_block_index;
+
+        // Start at next block on next iteration.
+        self.next_hdr_block_index += 1;
+
+        // We only update the block index for types that have a payload.
+        // In directory entries, for example, the size field has other
+        // semantics. See spec.
+        if hdr.typeflag.has_payload() {
+            let payload_size = match hdr.size.as_number::<u64>() {
+                Ok(payload_size) => payload_size,
+                Err(e) => {
+                    log_header!(
+                        error,
+                        block_index,
+                        hdr,
+                        "Unparsable size ({e:?}) in header {hdr:#?}"
+                    );
+                    self.next_hdr_block_index = usize::MAX;
+                    return Some(Err(TarError::UnparsableSize { block_index }));
+                }
+            };
+            // Payloads beyond the archive end the iteration with the next
+            // call.
+            let payload_block_count =
+                usize::try_from(blocks_for_payload(payload_size)).unwrap_or(usize::MAX);
+            self.next_hdr_block_index = self
+                .next_hdr_block_index
+                .saturating_add(payload_block_count)
+                .min(total_block_count);
+        }
+
+        Some(Ok((block_index, hdr)))
+    }
+}
+
+/// A header together with the extension records that precede it.
+///
+/// The extension records are PAX extended headers and GNU long names and
+/// long link targets. Unlike [`ArchiveEntry`], this covers entries of any
+/// type. See [`ArchiveHeaderIterator::logical_entries`].
+#[derive(Copy, Clone, Debug)]
+pub struct LogicalEntry<'a> {
+    first_block_index: BlockIndex,
+    block_index: BlockIndex,
+    header: &'a PosixHeader,
+    data: &'a [u8],
+    pax: Option<PaxHeader<'a>>,
+    long_name: Option<&'a [u8]>,
+    long_link_target: Option<&'a [u8]>,
+}
+
+impl<'a> LogicalEntry<'a> {
+    /// Major and minor device number of character and block devices. See
+    /// [`RawEntry::device_numbers`].
+    #[must_use]
+    pub fn device_numbers(&self) -> Option<(u64, u64)> {
+        device_numbers(self.header, false)
+    }
+
+    /// See [`RawEntry::device_numbers_lenient`].
+    #[must_use]
+    pub fn device_numbers_lenient(&self) -> Option<(u64, u64)> {
+        device_numbers(self.header, true)
+    }
+
+    /// Index of the block of the actual header, i.e., after the extension
+    /// records.
+    #[must_use]
+    pub const fn block_index(&self) -> usize {
+        self.block_index
+    }
+
+    /// Index of the first block of the entry, i.e., of its first extension
+    /// record, if there is one, and of the actual header otherwise. PAX
+    /// global headers don't belong to a single entry and are not considered.
+    #[must_use]
+    pub const fn first_block_index(&self) -> usize {
+        self.first_block_index
+    }
+
+    /// The actual header of the entry.
+    #[must_use]
+    pub const fn header(&self) -> &'a PosixHeader {
+        self.header
+    }
+
+    /// The typeflag of the actual header.
+    #[must_use]
+    pub const fn typeflag(&self) -> TypeFlagRaw {
+        self.header.typeflag
+    }
+
+    /// The payload of the entry. A `size` record of a PAX extended header
+    /// takes precedence over the size field of the header. This is empty for
+    /// types without payload, such as directories and links.
+    #[must_use]
+    pub const fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// The effective size of the payload in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// The PAX extended header records that apply to the entry, if there are
+    /// any. This includes the records of the most recent global extended
+    /// header.
+    #[must_use]
+    pub const fn pax(&self) -> Option<PaxHeader<'a>> {
+        self.pax
+    }
+
+    /// The full name of the entry, if it is stored in an extension record,
+    /// i.e., in the `path` record of a PAX extended header or in a GNU long
+    /// name. The PAX record takes precedence.
+    #[must_use]
+    pub const fn long_name(&self) -> Option<&'a [u8]> {
+        self.long_name
+    }
+
+    /// The full link target of the entry, if it is stored in an extension
+    /// record, i.e., in the `linkpath` record of a PAX extended header or in
+    /// a GNU long link. The PAX record takes precedence.
+    #[must_use]
+    pub const fn long_link_target(&self) -> Option<&'a [u8]> {
+        self.long_link_target
+    }
+
+    /// The effective filename with a maximum of 256 characters. This is the
+    /// [`Self::long_name`], if it is UTF-8 and fits, and the name of the
+    /// header otherwise.
+    #[must_use]
+    pub fn filename(&self) -> TarFormatString<POSIX_1003_MAX_FILENAME_LEN> {
+        self.long_name
+            .and_then(|name| {
+                core::str::from_utf8(name)
+                    .ok()
+                    .and_then(|name| TarFormatString::try_from_str(name).ok())
+                    .ok_or(())
+                    .inspect_err(|()| {
+                        log_header!(
+                            warn,
+                            self.block_index,
+                            self.header,
+                            "Long name {:?} is too long or not UTF-8, using the name of the header",
+                            name.escape_ascii()
+                        );
+                    })
+                    .ok()
+            })
+            .unwrap_or_else(|| filename_from_header(self.header))
+    }
+}
+
+/// Iterator over the entries of the archive with the extension records folded
+/// in. See [`ArchiveHeaderIterator::logical_entries`].
+///
+/// The iteration ends at the first header or payload that can't be parsed.
+#[derive(Debug)]
+pub struct LogicalEntryIterator<'a> {
+    headers: ArchiveHeaderIterator<'a>,
+    /// Payload of the PAX extended header (typeflag `x`) for the next entry.
+    pax_local: Option<&'a [u8]>,
+    /// Payload of the most recent PAX global header (typeflag `g`).
+    pax_global: Option<&'a [u8]>,
+    /// Name of the GNU long name record (typeflag `L`) for the next entry.
+    gnu_long_name: Option<&'a [u8]>,
+    /// Target of the GNU long link record (typeflag `K`) for the next entry.
+    gnu_long_link: Option<&'a [u8]>,
+    /// Block index of the first extension record for the next entry.
+    first_extension_block: Option<BlockIndex>,
+}
+
+impl<'a> LogicalEntryIterator<'a> {
+    const fn new(headers: ArchiveHeaderIterator<'a>) -> Self {
+        Self {
+            headers,
+            pax_local: None,
+            pax_global: None,
+            gnu_long_name: None,
+            gnu_long_link: None,
+            first_extension_block: None,
+        }
+    }
+
+    /// Like [`Iterator::next`] but reports why the iteration ended early.
+    /// The iteration ends after an error.
+    fn try_next(&mut self) -> Option<Result<LogicalEntry<'a>, TarError>> {
+        loop {
+            let (block_index, hdr) = match self.headers.try_next()? {
+                Ok(next) => next,
+                Err(e) => return Some(Err(e)),
+            };
+            let typeflag = hdr.typeflag.try_to_type_flag().ok();
+            match typeflag {
+                Some(
+                    typeflag @ (TypeFlag::XHDTYPE
+                    | TypeFlag::XGLTYPE
+                    | TypeFlag::GNU_LONGNAME
+                    | TypeFlag::GNU_LONGLINK),
+                ) => {
+                    let payload = match self.headers.payload(block_index, hdr)? {
+                        Ok(payload) => payload,
+                        Err(e) => return Some(Err(self.fail(e))),
+                    };
+                    // The GNU records are NUL-terminated.
+                    let len = payload
+                        .iter()
+                        .position(|&b| b == 0)
+                        .unwrap_or(payload.len());
+                    if typeflag != TypeFlag::XGLTYPE {
+                        self.first_extension_block.get_or_insert(block_index);
+                    }
+                    match typeflag {
+                        TypeFlag::XHDTYPE => self.pax_local = Some(payload),
+                        TypeFlag::XGLTYPE => self.pax_global = Some(payload),
+                        TypeFlag::GNU_LONGNAME => self.gnu_long_name = Some(&payload[..len]),
+                        _ => self.gnu_long_link = Some(&payload[..len]),
+                    }
+                }
+                _ => return Some(self.fold(block_index, hdr)),
+            }
+        }
+    }
+
+    /// Applies the pending extension records to the given header.
+    fn fold(
+        &mut self,
+        block_index: BlockIndex,
+        hdr: &'a PosixHeader,
+    ) -> Result<LogicalEntry<'a>, TarError> {
+        let pax = PaxHeader::new(self.pax_local.take(), self.pax_global);
+        let (gnu_long_name, gnu_long_link) = (self.gnu_long_name.take(), self.gnu_long_link.take());
+        let first_block_index = self.first_extension_block.take().unwrap_or(block_index);
+        let data = if hdr.typeflag.has_payload() {
+            let data = match pax.and_then(|pax| pax.size()) {
+                Some(size) => {
+                    // The header iterator skipped the payload according to
+                    // the size field of the header, which the record
+                    // overrides.
+                    let payload_block_count =
+                        usize::try_from(blocks_for_payload(size)).unwrap_or(usize::MAX);
+                    self.headers.next_hdr_block_index =
+                        (block_index + 1).saturating_add(payload_block_count);
+                    Some(self.headers.payload_of_size(block_index, hdr, size))
+                }
+                None => self.headers.payload(block_index, hdr),
+            };
+            match data {
+                Some(Ok(data)) => data,
+                Some(Err(e)) => return Err(self.fail(e)),
+                None => return Err(self.fail(TarError::UnparsableSize { block_index })),
+            }
+        } else {
+            &[]
+        };
+        Ok(LogicalEntry {
+            first_block_index,
+            block_index,
+            header: hdr,
+            data,
+            pax,
+            long_name: pax
+                .and_then(|pax| pax.path())
+                .map(str::as_bytes)
+                .or(gnu_long_name),
+            long_link_target: pax
+                .and_then(|pax| pax.linkpath())
+                .map(str::as_bytes)
+                .or(gnu_long_link),
+        })
+    }
+
+    /// Ends the iteration, as the position of the next header can't be
+    /// trusted after an error.
+    fn fail(&mut self, error: TarError) -> TarError {
+        self.headers.next_hdr_block_index = usize::MAX;
+        error
+    }
+}
+
+impl<'a> LogicalEntryIterator<'a> {
+    /// Converts an entry of this iterator into an [`ArchiveEntry`].
+    fn archive_entry(&self, entry: &LogicalEntry<'a>) -> ArchiveEntry<'a> {
+        let raw_blocks =
+            self.headers
+                .raw_blocks(entry.first_block_index, entry.block_index, entry.data.len());
+        let mut archive_entry = ArchiveEntry::new(
+            entry.filename(),
+            entry.data,
+            entry.header,
+            entry.block_index,
+            raw_blocks,
+        );
+        archive_entry.extension_len = (entry.block_index - entry.first_block_index) * BLOCKSIZE;
+        archive_entry.pax = entry.pax;
+        archive_entry.long_name = entry.long_name;
+        archive_entry.long_link_target = entry.long_link_target;
+        archive_entry
+    }
+}
+
+impl<'a> Iterator for LogicalEntryIterator<'a> {
+    type Item = LogicalEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next()?.ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.headers.size_hint().1)
+    }
+}
+
+/// Saved position of an [`ArchiveEntryIterator`].
+///
+/// This allows to suspend an iteration and to resume it later, e.g., across
+/// interrupts in event-driven firmware. See [`ArchiveEntryIterator::save`]
+/// and [`TarArchiveRef::restore`].
+///
+/// The state consists of the block index of the next header and the
+/// locations of the pending PAX extended headers, i.e., the global header
+/// that applies to all following entries and a local header whose entry
+/// wasn't reached yet. It only holds offsets into the archive and doesn't
+/// borrow it, so it can be stored anywhere.
+///
+/// The state must be restored with the same archive data that it was saved
+/// from. Other data doesn't cause undefined behavior, but the iteration
+/// yields garbage or ends early. [`ArchiveEntryIterator::skipped_count`] and
+/// [`ArchiveEntryIterator::skipped_types`] are not part of the state and
+/// start from zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParserState {
+    next_hdr_block_index: usize,
+    /// Offset and length of the payload of the pending local PAX header.
+    pax_local: Option<(usize, usize)>,
+    /// Offset and length of the payload of the most recent global PAX header.
+    pax_global: Option<(usize, usize)>,
+}
+
+impl ParserState {
+    /// The state at the beginning of an archive.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            next_hdr_block_index: 0,
+            pax_local: None,
+            pax_global: None,
+        }
+    }
+}
+
+/// Iterator over the files of the archive.
+///
+/// Only regular files are supported, but not directories, links, or other
+/// special types ([`crate::TypeFlag`]). The full path to files is reflected
+/// in their file name. Links are available via [`TarArchiveRef::links`].
+#[derive(Debug)]
+pub struct ArchiveEntryIterator<'a> {
+    entries: LogicalEntryIterator<'a>,
+    skipped_count: usize,
+    skipped_types: TypeFlagSet,
+}
+
+impl<'a> ArchiveEntryIterator<'a> {
+    fn new(headers: ArchiveHeaderIterator<'a>) -> Self {
+        Self::with_state(headers, ParserState::new())
+    }
+
+    fn with_state(mut headers: ArchiveHeaderIterator<'a>, state: ParserState) -> Self {
+        let archive = headers.archive_data;
+        let payload = |range: Option<(usize, usize)>| {
+            let (offset, len) = range?;
+            archive.get(offset..offset.checked_add(len)?)
+        };
+        headers.next_hdr_block_index = state.next_hdr_block_index;
+        let mut entries = headers.logical_entries();
+        entries.pax_local = payload(state.pax_local);
+        entries.pax_global = payload(state.pax_global);
+        Self {
+            entries,
+            skipped_count: 0,
+            skipped_types: TypeFlagSet::new(),
+        }
+    }
+
+    /// Index of the block of the next header, i.e., where the iteration
+    /// continues. Use [`TarArchiveRef::entries_from_block`] to resume the
+    /// iteration there. After an error, this is the number of blocks of the
+    /// archive.
+    #[must_use]
+    pub fn current_block(&self) -> usize {
+        let headers = &self.entries.headers;
+        headers
+            .next_hdr_block_index
+            .min(headers.archive_data.len() / BLOCKSIZE)
+    }
+
+    /// Saves the position of the iteration, so that it can be resumed later
+    /// with [`TarArchiveRef::restore`] without holding the iterator. See
+    /// [`ParserState`].
+    #[must_use]
+    pub fn save(&self) -> ParserState {
+        let archive = self.entries.headers.archive_data;
+        let range = |payload: Option<&[u8]>| {
+            payload.map(|payload| {
+                (
+                    payload.as_ptr() as usize - archive.as_ptr() as usize,
+                    payload.len(),
+                )
+            })
+        };
+        ParserState {
+            next_hdr_block_index: self.entries.headers.next_hdr_block_index,
+            pax_local: range(self.entries.pax_local),
+            pax_global: range(self.entries.pax_global),
+        }
+    }
+
+    /// Number of entries that were skipped so far, as they are not regular
+    /// files, such as directories and links. Read this after the iteration
+    /// to find out whether a richer extraction is needed. PAX extended
+    /// headers are applied to the entries and thus not counted.
+    #[must_use]
+    pub const fn skipped_count(&self) -> usize {
+        self.skipped_count
+    }
+
+    /// The typeflags of the entries counted by [`Self::skipped_count`]. An
+    /// invalid typeflag ends the iteration, but is included as well.
+    #[must_use]
+    pub const fn skipped_types(&self) -> TypeFlagSet {
+        self.skipped_types
+    }
+
+    /// Records an entry that isn't yielded.
+    fn skip(&mut self, hdr: &PosixHeader) {
+        self.skipped_count += 1;
+        self.skipped_types.insert(hdr.typeflag.as_byte());
+    }
+
+    /// Returns the next regular file together with the PAX extended header
+    /// records that apply to it.
+    fn next_with_pax(&mut self) -> Option<EntryWithPax<'a>> {
+        self.try_next_with_pax()?.ok()
+    }
+
+    /// Like [`Self::next_with_pax`] but reports malformed archives. The
+    /// iteration ends after an error.
+    fn try_next_with_pax(&mut self) -> Option<Result<EntryWithPax<'a>, TarError>> {
+        // Ignore directory entries, i.e. yield only regular files. Works as
+        // filenames in tarballs are fully specified, e.g. dirA/dirB/file1
+        let entry = loop {
+            let entry = match self.entries.try_next()? {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+            let (block_index, hdr) = (entry.block_index, entry.header);
+            // GNU multi-volume continuations hold the data of a regular file.
+            if hdr.typeflag.as_byte() == b'M' {
+                break entry;
+            }
+            match hdr.typeflag.try_to_type_flag() {
+                Ok(typeflag) if typeflag.is_file_like() => break entry,
+                Ok(_) => {
+                    log_header!(
+                        warn,
+                        block_index,
+                        hdr,
+                        "Skipping entry of type {:?} (not supported yet)",
+                        hdr.typeflag
+                    );
+                    self.skip(hdr);
+                }
+                Err(e) => {
+                    log_header!(error, block_index, hdr, "Invalid TypeFlag: {e:?}");
+                    self.skip(hdr);
+                    return Some(Err(self.entries.fail(TarError::InvalidTypeFlag {
+                        block_index,
+                        typeflag: hdr.typeflag.as_byte(),
+                    })));
+                }
+            }
+        };
+
+        Some(Ok((self.entries.archive_entry(&entry), entry.pax)))
+    }
+}
+
+/// A regular file together with the PAX extended header records that apply
+/// to it.
+type EntryWithPax<'a> = (ArchiveEntry<'a>, Option<PaxHeader<'a>>);
+
+impl<'a> Iterator for ArchiveEntryIterator<'a> {
+    type Item = ArchiveEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_pax().map(|(entry, _)| entry)
+    }
+
+    /// The number of remaining entries isn't known without parsing the
+    /// remaining headers. See [`TarArchiveRef::entry_count`].
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+/// Iterator over the files of the archive together with the PAX extended
+/// header records that apply to them. See [`TarArchiveRef::entries_with_pax`].
+#[derive(Debug)]
+pub struct PaxEntryIterator<'a>(ArchiveEntryIterator<'a>);
+
+impl<'a> Iterator for PaxEntryIterator<'a> {
+    type Item = (ArchiveEntry<'a>, Option<PaxHeader<'a>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_with_pax()
+    }
+}
+
+/// Iterator over the symlinks and hardlinks of the archive. See
+/// [`TarArchiveRef::links`].
+///
+/// GNU long names and long link targets as well as the `path` and
+/// `linkpath` records of PAX extended headers are applied. See
+/// [`ArchiveEntry::long_link_target`].
+#[derive(Debug)]
+pub struct LinkIterator<'a>(LogicalEntryIterator<'a>);
+
+impl<'a> Iterator for LinkIterator<'a> {
+    type Item = ArchiveEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.0.find(|entry| {
+            matches!(
+                entry.typeflag().try_to_type_flag(),
+                Ok(TypeFlag::SYMTYPE | TypeFlag::LINK)
+            )
+        })?;
+        Some(self.0.archive_entry(&entry))
+    }
+}
+
+/// An entry of any type together with its raw header. See
+/// [`TarArchiveRef::raw_entries`].
+#[derive(Copy, Clone, Debug)]
+pub struct RawEntry<'a> {
+    header: &'a PosixHeader,
+    data: &'a [u8],
+    typeflag: TypeFlag,
+}
+
+impl<'a> RawEntry<'a> {
+    /// The header of the entry.
+    #[must_use]
+    pub const fn header(&self) -> &'a PosixHeader {
+        self.header
+    }
+
+    /// The payload of the entry. This is empty for types without payload,
+    /// such as directories and links.
+    #[must_use]
+    pub const fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// The type of the entry.
+    #[must_use]
+    pub const fn typeflag(&self) -> TypeFlag {
+        self.typeflag
+    }
+
+    /// Major and minor device number of the entry, as stored in the octal
+    /// `dev_major` and `dev_minor` fields of the header. Empty fields are
+    /// reported as `0`.
+    ///
+    /// Returns `None` for entries other than character and block devices, so
+    /// that the device `0:0` can't be confused with an inapplicable entry,
+    /// and for fields that aren't valid octal numbers.
+    #[must_use]
+    pub fn device_numbers(&self) -> Option<(u64, u64)> {
+        device_numbers(self.header, false)
+    }
+
+    /// Like [`Self::device_numbers`] but falls back to hexadecimal for each
+    /// field that isn't a valid octal number.
+    ///
+    /// This is a compatibility shim for nonstandard tools that write the
+    /// device numbers in hex. As values such as `10` are valid in both
+    /// bases, they are always read as octal.
+    #[must_use]
+    pub fn device_numbers_lenient(&self) -> Option<(u64, u64)> {
+        device_numbers(self.header, true)
+    }
+}
+
+/// Iterator over all entries of the archive. See
+/// [`TarArchiveRef::raw_entries`].
+///
+/// Entries with an unknown typeflag are skipped, with their payload. The
+/// iteration ends at the first payload that doesn't fit into the archive.
+#[derive(Debug)]
+pub struct RawEntryIterator<'a>(ArchiveHeaderIterator<'a>);
+
+impl<'a> Iterator for RawEntryIterator<'a> {
+    type Item = RawEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (block_index, hdr) = self.0.next()?;
+            let Ok(typeflag) = hdr.typeflag.try_to_type_flag() else {
+                log_header!(
+                    warn,
+                    block_index,
+                    hdr,
+                    "Skipping entry of unknown type {:?}",
+                    hdr.typeflag
+                );
+                continue;
+            };
+            let data = if hdr.typeflag.has_payload() {
+                self.0.payload(block_index, hdr)?.ok()?
+            } else {
+                &[]
+            };
+            return Some(RawEntry {
+                header: hdr,
+                data,
+                typeflag,
+            });
+        }
+    }
+}
+
+/// Iterator over the files of the archive that reports errors instead of
+/// silently ending the iteration. See [`TarArchiveRef::try_entries`].
+#[derive(Debug)]
+pub struct TryEntryIterator<'a>(ArchiveEntryIterator<'a>);
+
+impl<'a> Iterator for TryEntryIterator<'a> {
+    type Item = Result<ArchiveEntry<'a>, TarError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .try_next_with_pax()
+            .map(|result| result.map(|(entry, _)| entry))
+    }
+}
+
+/// Reason why an iteration ended before the end of the archive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StopReason {
+    /// The cumulative size of the entries would exceed the limit.
+    SizeLimitReached,
+    /// The caller requested to stop the iteration.
+    Cancelled,
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::SizeLimitReached => f.write_str("The size limit was reached"),
+            Self::Cancelled => f.write_str("The iteration was cancelled"),
+        }
+    }
+}
+
+/// Iterator over the files of the archive that stops, once the cumulative
+/// size of the yielded entries would exceed a limit. See
+/// [`TarArchiveRef::entries_size_limited`].
+///
+/// This is a defense against archives whose entries sum up to an enormous
+/// amount of data, e.g., when extracting an untrusted initramfs into a RAM
+/// disk. A good limit is the memory that is actually available for the
+/// extracted files, such as the size of the RAM disk. The entry that would
+/// exceed the limit is not yielded. Use [`Self::stop_reason`] to find out
+/// whether the iteration ended early.
+#[derive(Debug)]
+pub struct SizeLimitedEntryIterator<'a> {
+    entries: ArchiveEntryIterator<'a>,
+    remaining: u64,
+    stop_reason: Option<StopReason>,
+}
+
+impl<'a> SizeLimitedEntryIterator<'a> {
+    const fn new(entries: ArchiveEntryIterator<'a>, max_total: u64) -> Self {
+        Self {
+            entries,
+            remaining: max_total,
+            stop_reason: None,
+        }
+    }
+
+    /// Returns why the iteration ended early, or `None`, if it didn't.
+    #[must_use]
+    pub const fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+}
+
+impl<'a> Iterator for SizeLimitedEntryIterator<'a> {
+    type Item = ArchiveEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stop_reason.is_some() {
+            return None;
+        }
+        let entry = self.entries.next()?;
+        match self.remaining.checked_sub(entry.size() as u64) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Some(entry)
+            }
+            None => {
+                warn!("Stopping iteration, as the size limit was reached");
+                self.stop_reason = Some(StopReason::SizeLimitReached);
+                None
+            }
+        }
+    }
+}
+
+/// Iterator over the files of the archive that can be cancelled by the
+/// caller. See [`TarArchiveRef::entries_until`].
+///
+/// This is a cooperative cancellation hook for long-running extractions,
+/// e.g., to react to an external signal in a cooperatively scheduled
+/// `no_std` environment. The callback is checked before the next header is
+/// parsed, so the iteration always stops between two entries and never
+/// mid-entry. Once it returns `false`, the iteration ends for good. Use
+/// [`Self::stop_reason`] to find out whether it was cancelled.
+pub struct CancellableEntryIterator<'a, F> {
+    entries: ArchiveEntryIterator<'a>,
+    should_continue: F,
+    stop_reason: Option<StopReason>,
+}
+
+impl<'a, F: FnMut() -> bool> CancellableEntryIterator<'a, F> {
+    const fn new(entries: ArchiveEntryIterator<'a>, should_continue: F) -> Self {
+        Self {
+            entries,
+            should_continue,
+            stop_reason: None,
+        }
+    }
+
+    /// Returns why the iteration ended early, or `None`, if it didn't.
+    #[must_use]
+    pub const fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+}
+
+impl<'a, F: FnMut() -> bool> Iterator for CancellableEntryIterator<'a, F> {
+    type Item = ArchiveEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stop_reason.is_some() {
+            return None;
+        }
+        if !(self.should_continue)() {
+            warn!("Stopping iteration, as it was cancelled");
+            self.stop_reason = Some(StopReason::Cancelled);
+            return None;
+        }
+        self.entries.next()
+    }
+}
+
+impl<F> Debug for CancellableEntryIterator<'_, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CancellableEntryIterator")
+            .field("entries", &self.entries)
+            .field("stop_reason", &self.stop_reason)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use std::string::String;
     use std::vec::Vec;
 
     #[test]
-    #[rustfmt::skip]
-    fn test_constructor_returns_error() {
-        assert_eq!(TarArchiveRef::new(&[0]), Err(CorruptDataError));
-        assert_eq!(TarArchiveRef::new(&[]), Err(CorruptDataError));
-        assert!(TarArchiveRef::new(&[0; BLOCKSIZE * MIN_BLOCK_COUNT]).is_ok());
+    #[rustfmt::skip]
+    fn test_constructor_returns_error() {
+        assert_eq!(TarArchiveRef::new(&[0]), Err(TarError::CorruptData(CorruptDataError)));
+        assert_eq!(TarArchiveRef::new(&[]), Err(TarError::CorruptData(CorruptDataError)));
+        assert!(TarArchiveRef::new(&[0; BLOCKSIZE * MIN_BLOCK_COUNT]).is_ok());
+
+        #[cfg(feature = "alloc")]
+        {
+            assert_eq!(TarArchive::new(vec![].into_boxed_slice()), Err(TarError::CorruptData(CorruptDataError)));
+            assert_eq!(TarArchive::new(vec![0].into_boxed_slice()), Err(TarError::CorruptData(CorruptDataError)));
+            assert!(TarArchive::new(vec![0; BLOCKSIZE * MIN_BLOCK_COUNT].into_boxed_slice()).is_ok());
+        };
+    }
+
+    #[test]
+    fn test_compressed_input() {
+        let compressed = |magic: &[u8]| {
+            let mut data = [0; BLOCKSIZE * MIN_BLOCK_COUNT];
+            data[..magic.len()].copy_from_slice(magic);
+            TarArchiveRef::new(&data).map(|_| ())
+        };
+        let error = |kind| Err(TarError::CompressedInput { kind });
+        assert_eq!(compressed(&[0x1f, 0x8b, 0x08]), error(Compression::Gzip));
+        assert_eq!(
+            compressed(&[0x28, 0xb5, 0x2f, 0xfd]),
+            error(Compression::Zstd)
+        );
+        assert_eq!(compressed(b"\xfd7zXZ\0"), error(Compression::Xz));
+        assert_eq!(compressed(b"BZh91AY&SY"), error(Compression::Bzip2));
+        // compressed data has an arbitrary length
+        assert_eq!(
+            TarArchiveRef::new(&[0x1f, 0x8b, 0x08, 0x00]).map(|_| ()),
+            error(Compression::Gzip)
+        );
+
+        // a valid header of a file whose name starts like a bzip2 stream
+        let mut data = include_bytes!("../tests/gnu_tar_default.tar").to_vec();
+        let hdr = unsafe { &mut *data.as_mut_ptr().cast::<PosixHeader>() };
+        hdr.name = TarFormatString::try_from_str("BZh.txt").unwrap();
+        hdr.recompute_checksum();
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(
+            archive.entries().next().unwrap().filename().as_str(),
+            Ok("BZh.txt")
+        );
+    }
+
+    #[test]
+    fn test_new_skip_junk() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new_skip_junk(data).unwrap();
+        assert_eq!(archive, TarArchiveRef::new(data).unwrap());
+
+        // UTF-8 BOM and a stray block of junk
+        for junk in [&b"\xEF\xBB\xBF"[..], &[b'#'; BLOCKSIZE + 7]] {
+            let mut mangled = junk.to_vec();
+            mangled.extend_from_slice(data);
+            assert!(TarArchiveRef::new(&mangled).is_err());
+            let archive = TarArchiveRef::new_skip_junk(&mangled).unwrap();
+            assert_eq!(archive.entries().count(), 3);
+        }
+
+        // too much junk
+        let mut mangled = std::vec![0xff; MAX_JUNK_BLOCKS * BLOCKSIZE];
+        mangled.extend_from_slice(data);
+        assert_eq!(
+            TarArchiveRef::new_skip_junk(&mangled),
+            Err(TarError::CorruptData(CorruptDataError))
+        );
+        assert_eq!(
+            TarArchiveRef::new_skip_junk(&[]),
+            Err(TarError::CorruptData(CorruptDataError))
+        );
+    }
+
+    #[test]
+    fn test_entries_size_limited() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        // The files have 513, 513, and 12 bytes.
+        for (max_total, count, stop_reason) in [
+            (1038, 3, None),
+            (u64::MAX, 3, None),
+            (1037, 2, Some(StopReason::SizeLimitReached)),
+            (513, 1, Some(StopReason::SizeLimitReached)),
+            (0, 0, Some(StopReason::SizeLimitReached)),
+        ] {
+            let mut entries = archive.entries_size_limited(max_total);
+            assert_eq!(entries.by_ref().count(), count);
+            assert_eq!(entries.stop_reason(), stop_reason);
+            assert!(entries.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_read_many() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let [hello, missing, bye, hello_again] = archive.read_many([
+            "hello_world.txt",
+            "missing",
+            "bye_world_513b.txt",
+            "hello_world.txt",
+        ]);
+        assert_eq!(hello, Some(b"Hello World\n".as_slice()));
+        assert_eq!(missing, None);
+        assert_eq!(bye.map(<[u8]>::len), Some(513));
+        assert_eq!(hello_again, hello);
+        assert_eq!(archive.read_many([]), []);
+
+        let files = extract_named!(archive, { hello: "hello_world.txt", missing: "missing" });
+        assert_eq!(files.hello, hello);
+        assert_eq!(files.missing, None);
+    }
+
+    #[test]
+    fn test_contains_all() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        assert!(archive.contains_all(&[]));
+        assert!(archive.contains_all(&["hello_world.txt", "bye_world_513b.txt"]));
+        assert!(!archive.contains_all(&["hello_world.txt", "kernel"]));
+
+        let names = ["kernel", "hello_world.txt", "initrd", "hello_world.txt"];
+        let missing = archive.missing_names(&names).collect::<Vec<_>>();
+        assert_eq!(missing, ["kernel", "initrd"]);
+
+        // more names than fit into a single scan
+        let mut names = [""; 70];
+        names[68] = "hello_world.txt";
+        names[69] = "kernel";
+        assert_eq!(archive.missing_names(&names).count(), 69);
+        assert_eq!(archive.missing_names(&names).last(), Some("kernel"));
+        assert!(archive.contains_all(&names[68..69]));
+    }
+
+    #[test]
+    fn test_block_padding() {
+        assert_eq!(block_padding(0), 0);
+        assert_eq!(block_padding(1), 511);
+        assert_eq!(block_padding(511), 1);
+        assert_eq!(block_padding(512), 0);
+        assert_eq!(block_padding(513), 511);
+        assert_eq!(block_padding(u64::MAX), 1);
+        assert_eq!(blocks_for_payload(0), 0);
+        assert_eq!(blocks_for_payload(1), 1);
+        assert_eq!(blocks_for_payload(512), 1);
+        assert_eq!(blocks_for_payload(513), 2);
+    }
+
+    #[test]
+    fn test_entries_until() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        for (budget, count, stop_reason) in [
+            (4, 3, None),
+            (2, 2, Some(StopReason::Cancelled)),
+            (0, 0, Some(StopReason::Cancelled)),
+        ] {
+            let mut remaining = budget;
+            let mut entries = archive.entries_until(|| {
+                remaining -= 1;
+                remaining >= 0
+            });
+            assert_eq!(entries.by_ref().count(), count);
+            assert_eq!(entries.stop_reason(), stop_reason);
+            assert!(entries.next().is_none());
+        }
+    }
+
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[test]
+    fn test_entries_shared() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchive::new(data.to_vec().into_boxed_slice()).unwrap();
+        let archive = archive.into_shared();
+        assert_eq!(archive.entries().count(), 3);
+        let entries = archive.entries_shared().collect::<Vec<_>>();
+        let arc = archive.data_arc();
+        drop(archive);
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].0.as_str(), Ok("hello_world.txt"));
+        assert_eq!(&*entries[2].1, b"Hello World\n");
+        assert_eq!(&arc[entries[2].1.range()], b"Hello World\n");
+        // one reference of `arc` and one per entry
+        assert_eq!(Arc::strong_count(&arc), 4);
+        drop(entries);
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert_eq!(&*arc, data);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rebuild() {
+        let data = include_bytes!("../tests/gnu_tar_ustar_long.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let rebuilt = archive.rebuild().unwrap();
+        let rebuilt = TarArchiveRef::new(&rebuilt).unwrap();
+        assert_eq!(rebuilt.entries().count(), archive.entries().count());
+        for (original, rebuilt) in archive.entries().zip(rebuilt.entries()) {
+            let (hdr, rebuilt_hdr) = (original.posix_header(), rebuilt.posix_header());
+            assert_eq!(original.filename(), rebuilt.filename());
+            assert_eq!(original.data(), rebuilt.data());
+            assert_eq!(hdr.mode, rebuilt_hdr.mode);
+            assert_eq!(hdr.uid.as_number::<u64>(), rebuilt_hdr.uid.as_number());
+            assert_eq!(hdr.gid.as_number::<u64>(), rebuilt_hdr.gid.as_number());
+            assert_eq!(hdr.mtime.as_number::<u64>(), rebuilt_hdr.mtime.as_number());
+            assert_eq!(hdr.uname, rebuilt_hdr.uname);
+            assert_eq!(hdr.gname, rebuilt_hdr.gname);
+        }
+
+        // Archives of the builder are already normalized.
+        let mut builder = TarBuilder::new();
+        builder.append_file("a/b.txt", b"Hello").unwrap();
+        builder.append_file("c.txt", b"World").unwrap();
+        let data = builder.finish();
+        let rebuilt = TarArchiveRef::new(&data).unwrap().rebuild().unwrap();
+        assert_eq!(*data, *rebuilt);
+    }
+
+    /// The data of the last file ends exactly at the terminating zero blocks.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rebuild_empty_last_file() {
+        let mut builder = TarBuilder::new();
+        builder.append_file("a/b.txt", b"Hello").unwrap();
+        builder.append_file("c.txt", b"").unwrap();
+        let data = builder.finish();
+        let rebuilt = TarArchiveRef::new(&data).unwrap().rebuild().unwrap();
+        assert_eq!(*data, *rebuilt);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rebuild_long_name() {
+        let long_name = "0123456789/".repeat(27) + "file.txt";
+        let mut builder = TarBuilder::with_format(crate::TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        builder.append_file("short.txt", b"short").unwrap();
+        let data = builder.finish();
+        let rebuilt = TarArchiveRef::new(&data).unwrap().rebuild().unwrap();
+        let rebuilt = TarArchiveRef::new(&rebuilt).unwrap();
+        let entries = rebuilt.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].filename_string(), long_name);
+        assert_eq!(entries[0].data(), b"long");
+        assert_eq!(entries[1].filename_string(), "short.txt");
+        assert_eq!(rebuilt.format(), ArchiveFormat::Gnu);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rebuild_large_number() {
+        let options = EntryOptions {
+            uid: 1 << 40,
+            ..Default::default()
+        };
+        let mut builder = TarBuilder::with_format(crate::TarFormat::Gnu);
+        builder.append_file_with("file", &options, b"abc").unwrap();
+        let data = builder.finish();
+        let rebuilt = TarArchiveRef::new(&data).unwrap().rebuild().unwrap();
+        let rebuilt = TarArchiveRef::new(&rebuilt).unwrap();
+        let entries = rebuilt.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].uid(), Some(1 << 40));
+        assert_eq!(rebuilt.format(), ArchiveFormat::Gnu);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rebuild_pax_overrides() {
+        let mut builder = TarBuilder::new();
+        builder
+            .append_file(
+                "PaxHeaders/file",
+                b"12 uid=1000\n12 gid=1001\n20 mtime=1633330466\n",
+            )
+            .unwrap();
+        builder.append_file("file", b"abc").unwrap();
+        let mut data = builder.finish();
+        let mut hdr = PosixHeader::from_block(&data[..BLOCKSIZE]).unwrap();
+        hdr.typeflag = TypeFlag::XHDTYPE.into();
+        hdr.recompute_checksum();
+        data[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let rebuilt = archive.rebuild().unwrap();
+        let rebuilt = TarArchiveRef::new(&rebuilt).unwrap();
+        let entries = rebuilt.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].pax(), None);
+        let hdr = entries[0].posix_header();
+        assert_eq!(hdr.uid.as_number::<u64>(), Ok(1000));
+        assert_eq!(hdr.gid.as_number::<u64>(), Ok(1001));
+        assert_eq!(hdr.mtime.as_number::<u64>(), Ok(1_633_330_466));
+        assert_eq!(entries[0].data(), b"abc");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_rebuild_invalid_name() {
+        let mut builder = TarBuilder::new();
+        builder.append_file("a.txt", b"Hello").unwrap();
+        let mut data = builder.finish();
+        let mut hdr = PosixHeader::from_block(&data[..BLOCKSIZE]).unwrap();
+        let mut name = [0; NAME_LEN];
+        name[..5].copy_from_slice(b"\xff.txt");
+        hdr.name = TarFormatString::new(name);
+        hdr.recompute_checksum();
+        data[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.rebuild(), Err(BuildError::InvalidName));
+    }
+
+    #[test]
+    fn test_skipped_entries() {
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_links.tar")).unwrap();
+        let mut entries = archive.entries();
+        assert_eq!(entries.skipped_count(), 0);
+        assert!(entries.skipped_types().is_empty());
+        let files = entries.by_ref().count();
+        let headers = ArchiveHeaderIterator::new(archive.data).count();
+        assert_eq!(entries.skipped_count(), headers - files);
+        assert_eq!(
+            entries.skipped_types().iter().collect::<Vec<_>>(),
+            [b'1', b'2', b'5']
+        );
+
+        // PAX headers are applied and not skipped
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax.tar")).unwrap();
+        let mut entries = archive.entries();
+        entries.by_ref().for_each(drop);
+        assert_eq!(entries.skipped_count(), 0);
+    }
+
+    #[test]
+    fn test_raw_entries() {
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default_with_dir.tar")).unwrap();
+        let entries = archive
+            .raw_entries()
+            .map(|entry| {
+                (
+                    entry.header().name.as_str().unwrap(),
+                    entry.typeflag(),
+                    entry.data().len(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                ("tests/", TypeFlag::DIRTYPE, 0),
+                ("tests/hello_world.txt", TypeFlag::REGTYPE, 12),
+                ("tests/bye_world_513b.txt", TypeFlag::REGTYPE, 513),
+                ("tests/hello_world_513b.txt", TypeFlag::REGTYPE, 513),
+            ]
+        );
+        let hello = archive.raw_entries().nth(1).unwrap();
+        assert_eq!(hello.data(), b"Hello World\n");
+    }
+
+    #[test]
+    fn test_links() {
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_links.tar")).unwrap();
+        let links = archive
+            .links()
+            .map(|entry| {
+                let filename = String::from(entry.filename().as_str().unwrap());
+                let target = String::from(entry.link_target().unwrap().as_str().unwrap());
+                (filename, target, entry.size())
+            })
+            .collect::<Vec<_>>();
+        let expected = [
+            ("links/dir/relative_symlink", "../symlink"),
+            ("links/hello_world.txt", "links/hardlink"),
+            ("links/loop_a", "loop_b"),
+            ("links/loop_b", "loop_a"),
+            ("links/self_loop", "self_loop"),
+            ("links/symlink", "hello_world.txt"),
+        ]
+        .map(|(filename, target)| (filename.into(), target.into(), 0));
+        assert_eq!(links, expected);
+
+        // regular files have no target
+        let entry = archive.entries().next().unwrap();
+        assert_eq!(entry.filename().as_str(), Ok("links/hardlink"));
+        assert_eq!(entry.link_target(), None);
+    }
+
+    #[test]
+    fn test_links_long() {
+        let data = include_bytes!("../tests/gnu_tar_gnu_long.tar");
+        let long_name = "b".repeat(60) + "/" + &"c".repeat(60);
+        let link_name = "link_".to_string() + &"d".repeat(100);
+        let archive = TarArchiveRef::new(data).unwrap();
+        let links = archive.links().collect::<Vec<_>>();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].filename().as_str(), Ok(link_name.as_str()));
+        assert_eq!(links[0].long_link_target(), Some(long_name.as_bytes()));
+    }
+
+    #[test]
+    fn test_data_ptr_len() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let entry = archive.entries().last().unwrap();
+        let (ptr, len) = entry.data_ptr_len();
+        assert_eq!(len, 12);
+        assert_eq!(
+            ptr,
+            data[entry.header_block_index * BLOCKSIZE + BLOCKSIZE..].as_ptr()
+        );
+        let data =
+            unsafe { core::slice::from_raw_parts(ptr.cast::<core::mem::MaybeUninit<u8>>(), len) };
+        assert_eq!(unsafe { data[0].assume_init() }, b'H');
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_size_encoding() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.entries().next().unwrap();
+        assert_eq!(entry.size_encoding(), SizeEncoding::Octal);
+
+        let mut archive = crate::TarBuilder::new();
+        archive.append_file("file", &[]).unwrap();
+        let mut archive = archive.finish();
+        archive[124..136].copy_from_slice(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut hdr = *ArchiveHeaderIterator::new(&archive).next().unwrap().1;
+        hdr.recompute_checksum();
+        archive[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+        let archive = TarArchiveRef::new(&archive).unwrap();
+        let entry = archive.entries().next().unwrap();
+        assert_eq!(entry.size_encoding(), SizeEncoding::Base256);
+    }
+
+    #[test]
+    fn test_raw_size_field() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.entries().next().unwrap();
+        assert_eq!(entry.raw_size_field(), b"00000001001\0");
+
+        // Logging a header with a non-UTF-8 size field must not panic.
+        let mut hdr = *entry.posix_header();
+        hdr.size = TarFormatOctal::new(*b"\x80\0\0\0\0\0\0\0\0\0\x02\x01");
+        assert!(std::format!("{hdr:?}").contains(r"[\x80]"));
+    }
+
+    #[test]
+    fn test_data_reader() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        for entry in archive.entries() {
+            let mut reader = entry.data_reader();
+            assert_eq!(reader.remaining_bytes(), entry.size());
+            let mut data = Vec::new();
+            loop {
+                let len = reader.remaining_bytes().min(BLOCKSIZE);
+                let Some(block) = reader.next_block() else {
+                    break;
+                };
+                data.extend_from_slice(&block[..len]);
+            }
+            assert_eq!(data, entry.data());
+            assert_eq!(reader.remaining_bytes(), 0);
+        }
+
+        let entry = archive.find("bye_world_513b.txt").unwrap();
+        assert_eq!(entry.data_reader().count(), 2);
+        assert_eq!(entry.data_reader().last_block_valid_len(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.find("hello_world.txt").unwrap();
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            r#"{"filename":"hello_world.txt","size":12,"typeflag":"0","mode":420,"mtime":1633330476,"uid":1001,"gid":1001}"#
+        );
+        let json = serde_json::to_string(entry.posix_header()).unwrap();
+        assert!(json.starts_with(r#"{"name":"hello_world.txt","mode":420,"uid":1001,"#));
+        assert!(json.contains(r#""magic":"ustar ","version":" ","uname":"pschuster""#));
+    }
+
+    #[test]
+    fn test_modification_time() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive
+            .entries()
+            .find(|entry| entry.filename().as_str() == Ok("hello_world.txt"))
+            .unwrap();
+        // 2021-10-04 06:54:36 UTC
+        assert_eq!(entry.modification_time(), Ok(1_633_330_476));
+        assert_eq!(entry.mtime(), Some(1_633_330_476));
+        assert!(std::format!("{entry:?}").contains("1633330476 seconds since 1970"));
+
+        // The PAX record takes precedence, also in the debug output.
+        let mut entry = entry;
+        entry.pax = PaxHeader::new(Some(b"16 mtime=-86400\n"), None);
+        assert_eq!(entry.mtime(), Some(-86_400));
+        assert!(std::format!("{entry:?}").contains("-86400 seconds since 1970"));
+        entry.pax = PaxHeader::new(Some(b"14 mtime=1000\n"), None);
+        assert_eq!(entry.modification_time(), Ok(1000));
+
+        // A corrupt field is reported as error.
+        let mut hdr = *entry.posix_header();
+        hdr.mtime = TarFormatOctal::new(*b"0000000000x\0");
+        entry.posix_header = &hdr;
+        entry.pax = None;
+        assert!(entry.modification_time().is_err());
+        assert_eq!(entry.mtime(), None);
+    }
+
+    #[test]
+    fn test_owner() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar.tar")).unwrap();
+        for entry in archive.entries() {
+            assert_eq!(entry.uid(), Some(1001));
+            assert_eq!(entry.gid(), Some(1001));
+            assert_eq!(entry.user_name(), Ok("pschuster"));
+            assert_eq!(entry.group_name(), Ok("pschuster"));
+        }
+    }
+
+    /// The data of the last file ends exactly where the terminating zero
+    /// blocks begin.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_data_ends_at_terminator() {
+        for data in [&[b'a'; BLOCKSIZE][..], &[b'a'; 2 * BLOCKSIZE], &[]] {
+            let mut builder = crate::TarBuilder::new();
+            builder.append_file("file", data).unwrap();
+            let archive = builder.finish();
+            let archive = TarArchiveRef::new(&archive).unwrap();
+            let last = archive.entries().last().unwrap();
+            assert_eq!(
+                (last.header_block_index + 1) * BLOCKSIZE + last.size(),
+                archive.data.len() - 2 * BLOCKSIZE
+            );
+            assert_eq!(last.data(), data);
+
+            let mut entries = archive.try_entries();
+            assert!(entries.next().unwrap().is_ok());
+            assert!(entries.next().is_none());
+        }
+
+        // One byte more runs into the terminator.
+        let mut archive = crate::TarBuilder::new();
+        archive.append_file("file", &[b'a'; BLOCKSIZE]).unwrap();
+        let mut archive = archive.finish();
+        archive[124..136].copy_from_slice(b"00000001001\0");
+        let mut hdr = *ArchiveHeaderIterator::new(&archive).next().unwrap().1;
+        hdr.recompute_checksum();
+        archive[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+        let archive = TarArchiveRef::new(&archive).unwrap();
+        assert!(matches!(
+            archive.try_entries().next(),
+            Some(Err(TarError::PayloadTooLarge { size: 513 }))
+        ));
+    }
+
+    #[test]
+    fn test_classified_blocks() {
+        use BlockClass::{Data, Header, Unknown, Zero};
+
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let classes = archive.classified_blocks().collect::<Vec<_>>();
+        assert_eq!(classes.len(), data.len() / BLOCKSIZE);
+        assert_eq!(
+            classes[..9],
+            [Header, Data, Data, Header, Data, Data, Header, Data, Zero]
+        );
+        assert!(classes[9..].iter().all(|class| *class == Zero));
+
+        // corrupt checksum of the second header and junk after the end
+        let mut data = data.to_vec();
+        data[3 * BLOCKSIZE + 148] = b'7';
+        let last = data.len() - BLOCKSIZE;
+        data[last] = 1;
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let classes = archive.classified_blocks().collect::<Vec<_>>();
+        assert_eq!(
+            classes[..9],
+            [Header, Data, Data, Unknown, Unknown, Unknown, Header, Data, Zero]
+        );
+        assert_eq!(classes.last(), Some(&Unknown));
+    }
+
+    #[test]
+    fn test_read() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        assert_eq!(
+            archive.read("hello_world.txt"),
+            Some(b"Hello World\n".as_slice())
+        );
+        assert_eq!(
+            archive.read("bye_world_513b.txt").map(<[u8]>::len),
+            Some(513)
+        );
+        assert_eq!(archive.read("hello_world"), None);
+        assert_eq!(archive.read(""), None);
+
+        // names with a ustar prefix
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_long.tar")).unwrap();
+        let entry = archive.entries().last().unwrap();
+        assert!(!entry.posix_header().prefix.is_empty());
+        let name = entry.filename();
+        assert_eq!(archive.read(name.as_str().unwrap()), Some(entry.data()));
+    }
+
+    #[test]
+    fn test_entries_matching() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let names = |entries: &mut dyn Iterator<Item = ArchiveEntry>| {
+            entries
+                .map(|entry| entry.filename().as_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let all = [
+            "bye_world_513b.txt",
+            "hello_world_513b.txt",
+            "hello_world.txt",
+        ];
+        assert_eq!(names(&mut archive.entries_matching("*.txt")), all);
+        assert_eq!(names(&mut archive.entries_matching("*")), all);
+        assert_eq!(
+            names(&mut archive.entries_matching("hello*")),
+            ["hello_world_513b.txt", "hello_world.txt"]
+        );
+        assert_eq!(
+            names(&mut archive.entries_matching("?ye_*_513b.txt")),
+            ["bye_world_513b.txt"]
+        );
+        assert_eq!(
+            names(&mut archive.entries_matching("*world.txt")),
+            ["hello_world.txt"]
+        );
+        assert!(names(&mut archive.entries_matching("")).is_empty());
+        assert!(names(&mut archive.entries_matching("*.TXT")).is_empty());
+        assert!(names(&mut archive.entries_matching("?hello_world.txt")).is_empty());
+        assert_eq!(
+            names(&mut archive.entries_matching_ignore_ascii_case("*.TXT")),
+            all
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.find("hello_world.txt").unwrap();
+        assert_eq!(entry.filename().as_str(), Ok("hello_world.txt"));
+        assert_eq!(entry.data(), b"Hello World\n");
+        assert!(archive.find("missing.txt").is_none());
+
+        let entry = archive.find_by(|entry| entry.size() == 513).unwrap();
+        assert_eq!(entry.filename().as_str(), Ok("bye_world_513b.txt"));
+        assert!(archive.find_by(|entry| entry.size() == 0).is_none());
+    }
+
+    #[test]
+    fn test_total_payload_bytes() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        assert_eq!(archive.total_payload_bytes(), Ok(513 + 513 + 12));
+
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default_with_dir.tar")).unwrap();
+        let sizes = archive.entries().map(|entry| entry.size() as u64);
+        assert_eq!(archive.total_payload_bytes(), Ok(sizes.sum()));
+
+        // An unparsable size field
+        let mut data = include_bytes!("../tests/gnu_tar_default.tar").to_vec();
+        data[124..136].copy_from_slice(b"not a size\0\0");
+        let mut hdr = PosixHeader::from_block(&data[..BLOCKSIZE]).unwrap();
+        hdr.recompute_checksum();
+        data[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(
+            archive.total_payload_bytes(),
+            Err(TarError::UnparsableSize { block_index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_entry_count() {
+        for data in [
+            &include_bytes!("../tests/gnu_tar_default.tar")[..],
+            include_bytes!("../tests/gnu_tar_default_with_dir.tar"),
+            include_bytes!("../tests/gnu_tar_ustar_links.tar"),
+        ] {
+            let archive = TarArchiveRef::new(data).unwrap();
+            assert_eq!(archive.entry_count(), archive.entries().count());
+            assert!(!archive.is_empty());
+        }
+        assert_eq!(
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar"))
+                .unwrap()
+                .entry_count(),
+            3
+        );
+
+        let empty = [0; BLOCKSIZE * MIN_BLOCK_COUNT];
+        let archive = TarArchiveRef::new(&empty).unwrap();
+        assert_eq!(archive.entry_count(), 0);
+        assert!(archive.is_empty());
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let mut entries = archive.entries();
+        let mut upper_bound = data.len() / BLOCKSIZE;
+        assert_eq!(entries.size_hint(), (0, Some(upper_bound)));
+        while entries.next().is_some() {
+            let (lower, upper) = entries.size_hint();
+            assert_eq!(lower, 0);
+            assert!(upper.unwrap() < upper_bound);
+            upper_bound = upper.unwrap();
+        }
+        assert_eq!(entries.size_hint(), (0, Some(0)));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_into_entries() {
+        let data = include_bytes!("../tests/gnu_tar_ustar_links.tar");
+        let archive = TarArchive::new(data.to_vec().into_boxed_slice()).unwrap();
+        let expected = TarArchiveRef::new(data)
+            .unwrap()
+            .entries()
+            .map(|entry| (entry.filename_string(), entry.data().to_vec()))
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+
+        let entries = archive
+            .into_entries()
+            .map(|entry| (entry.filename().to_string(), entry.into_data().into_vec()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_into_entries_owned() {
+        let data = include_bytes!("../tests/gnu_tar_ustar_long.tar");
+        let archive = TarArchive::new(data.to_vec().into_boxed_slice()).unwrap();
+        let expected = TarArchiveRef::new(data)
+            .unwrap()
+            .entries()
+            .map(|entry| {
+                let mode = entry.posix_header().mode.to_flags().unwrap();
+                (entry.filename_string(), mode, entry.data().to_vec())
+            })
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+
+        let entries = archive
+            .into_entries_owned()
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.filename().to_string(),
+                    entry.mode().unwrap(),
+                    entry.data().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_into_entries_long_name() {
+        let long_name = "0123456789/".repeat(27) + "file.txt";
+        let mut builder = TarBuilder::with_format(crate::TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        let archive = TarArchive::new(builder.finish()).unwrap();
+        let entries = archive.into_entries_owned();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename(), long_name);
+        assert_eq!(entries[0].data(), b"long");
+    }
+
+    #[test]
+    fn test_save_restore() {
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax_global.tar")).unwrap();
+        let expected = archive
+            .entries_with_pax()
+            .map(|(entry, pax)| (entry.filename(), pax))
+            .collect::<Vec<_>>();
+        assert!(expected.iter().any(|(_, pax)| pax.is_some()));
+
+        let mut state = ParserState::new();
+        let mut entries = Vec::new();
+        loop {
+            let mut iter = PaxEntryIterator(archive.restore(state));
+            let Some((entry, pax)) = iter.next() else {
+                break;
+            };
+            entries.push((entry.filename(), pax));
+            state = iter.0.save();
+        }
+        assert_eq!(entries, expected);
+
+        // a state of other data doesn't panic
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let state = ParserState {
+            next_hdr_block_index: usize::MAX,
+            pax_local: Some((usize::MAX, 1)),
+            pax_global: Some((0, usize::MAX)),
+        };
+        assert_eq!(archive.restore(state).count(), 0);
+    }
+
+    #[test]
+    fn test_entries_from_block() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let mut entries = archive.entries();
+        assert_eq!(entries.current_block(), 0);
+        let first = entries.next().unwrap();
+        assert_eq!(first.filename().as_str(), Ok("bye_world_513b.txt"));
+        let block_index = entries.current_block();
+        assert_eq!(block_index, 3);
+
+        let rest = archive
+            .entries_from_block(block_index)
+            .unwrap()
+            .map(|entry| entry.filename())
+            .collect::<Vec<_>>();
+        let expected = archive
+            .entries()
+            .skip(1)
+            .map(|entry| entry.filename())
+            .collect::<Vec<_>>();
+        assert_eq!(rest, expected);
+
+        // the terminator and beyond
+        let mut entries = archive.entries();
+        assert_eq!(entries.by_ref().count(), 3);
+        let end = entries.current_block();
+        assert_eq!(end, 8);
+        assert_eq!(archive.entries_from_block(end).unwrap().count(), 0);
+        assert!(archive.entries_from_block(data.len() / BLOCKSIZE).is_none());
+    }
+
+    #[test]
+    fn test_new_lenient() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        // cut within the payload of the second file
+        let truncated = &data[..4 * BLOCKSIZE + 100];
+        assert_eq!(
+            TarArchiveRef::new(truncated),
+            Err(TarError::CorruptData(CorruptDataError))
+        );
+        let archive = TarArchiveRef::new_lenient(truncated).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].is_truncated());
+        assert_eq!(entries[0].data().len(), 513);
+        assert_eq!(entries[1].filename().as_str(), Ok("hello_world_513b.txt"));
+        assert!(entries[1].is_truncated());
+        assert_eq!(entries[1].data(), &data[4 * BLOCKSIZE..4 * BLOCKSIZE + 100]);
+        let mut reader = entries[1].data_reader();
+        assert_eq!(reader.next_block(), None);
+        // cut within the padding of the last data block
+        let truncated = &data[..5 * BLOCKSIZE + 1];
+        let archive = TarArchiveRef::new_lenient(truncated).unwrap();
+        let entry = archive.entries().nth(1).unwrap();
+        assert_eq!(entry.data().len(), BLOCKSIZE + 1);
+        let mut reader = entry.data_reader();
+        assert_eq!(
+            reader.next_block(),
+            Some(&data[4 * BLOCKSIZE..5 * BLOCKSIZE].try_into().unwrap())
+        );
+        assert_eq!(reader.next_block(), None);
+        assert_eq!(archive.try_entries().count(), 2);
+
+        // cut at a block boundary without terminator
+        let archive = TarArchiveRef::new_lenient(&data[..6 * BLOCKSIZE]).unwrap();
+        assert!(archive.entries().all(|entry| !entry.is_truncated()));
+        assert_eq!(archive.entries().count(), 2);
+
+        // intact archives are unaffected
+        let archive = TarArchiveRef::new_lenient(data).unwrap();
+        assert_eq!(archive.entries().count(), 3);
+        assert!(TarArchiveRef::new_lenient(&data[..BLOCKSIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn test_members() {
+        let first = include_bytes!("../tests/gnu_tar_default.tar");
+        let second = include_bytes!("../tests/gnu_tar_ustar_links.tar");
+        let mut data = [&first[..], &second[..]].concat();
+        let filenames = |archive: &TarArchiveRef| {
+            archive
+                .entries()
+                .map(|entry| entry.filename())
+                .collect::<Vec<_>>()
+        };
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        // the iteration stops at the first terminator
+        assert_eq!(
+            filenames(&archive),
+            filenames(&TarArchiveRef::new(first).unwrap())
+        );
+        let members = archive.members().collect::<Vec<_>>();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].content_size(), archive.content_size());
+        assert_eq!(members[1], TarArchiveRef::new(second).unwrap());
+        let all = archive
+            .members()
+            .flat_map(|member| filenames(&member))
+            .collect::<Vec<_>>();
+        let expected = [
+            filenames(&TarArchiveRef::new(first).unwrap()),
+            filenames(&TarArchiveRef::new(second).unwrap()),
+        ]
+        .concat();
+        assert_eq!(all, expected);
+
+        // trailing data that isn't an archive, e.g., a signature
+        data.truncate(first.len());
+        data.extend_from_slice(&[0xab; BLOCKSIZE]);
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.members().count(), 1);
+    }
+
+    #[test]
+    fn test_new_validated() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        assert!(TarArchiveRef::new_validated(data).is_ok());
+
+        // corrupt the name of the second file
+        let mut data = data.to_vec();
+        data[3 * BLOCKSIZE] = b'X';
+        assert!(TarArchiveRef::new(&data).is_ok());
+        let error = TarArchiveRef::new_validated(&data).unwrap_err();
+        assert!(matches!(
+            error,
+            TarError::InvalidChecksum { block_index: 3, error } if error.stored.is_some()
+        ));
+    }
+
+    #[test]
+    fn test_validate_full() {
+        let fixtures: [&[u8]; 6] = [
+            include_bytes!("../tests/gnu_tar_default.tar"),
+            include_bytes!("../tests/gnu_tar_gnu_long.tar"),
+            include_bytes!("../tests/gnu_tar_multivolume_last.tar"),
+            include_bytes!("../tests/gnu_tar_pax.tar"),
+            include_bytes!("../tests/gnu_tar_ustar_long.tar"),
+            include_bytes!("../tests/weird_fuzzing_tarballs.tar"),
+        ];
+        for data in fixtures {
+            assert_eq!(TarArchiveRef::new(data).unwrap().validate_full(), Ok(()));
+        }
+
+        // corrupt the name of the second file
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let mut corrupt = data.to_vec();
+        corrupt[3 * BLOCKSIZE] = b'X';
+        let error = TarArchiveRef::new(&corrupt).unwrap().validate_full();
+        assert!(matches!(
+            error,
+            Err(FullValidationError {
+                block_index: 3,
+                error: TarError::InvalidChecksum { block_index: 3, .. }
+            })
+        ));
+
+        // truncated archive
+        let error = TarArchiveRef::new(&data[..4096])
+            .unwrap()
+            .validate_full()
+            .unwrap_err();
+        assert_eq!(error.block_index, 6);
+        assert_eq!(error.error, TarError::PayloadTooLarge { size: 12 });
+
+        let main_tarball =
+            TarArchiveRef::new(include_bytes!("../tests/weird_fuzzing_tarballs.tar")).unwrap();
+        // Every fuzzing input has at least one corrupt header.
+        assert!(main_tarball.entries().all(|tarball| {
+            let archive = TarArchiveRef::new(tarball.data()).unwrap();
+            archive.validate_full().is_err()
+        }));
+
+        // an unknown typeflag with a valid checksum
+        let mut hdr = PosixHeader::from_block(&data[3 * BLOCKSIZE..4 * BLOCKSIZE]).unwrap();
+        hdr.typeflag = TypeFlagRaw::from_byte(b'?');
+        hdr.recompute_checksum();
+        let mut corrupt = data.to_vec();
+        corrupt[3 * BLOCKSIZE..4 * BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+        let error = TarArchiveRef::new(&corrupt).unwrap().validate_full();
+        assert_eq!(
+            error,
+            Err(FullValidationError {
+                block_index: 3,
+                error: TarError::InvalidTypeFlag {
+                    block_index: 3,
+                    typeflag: b'?'
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_with_block_size() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        for block_size in [512, 1024, 10240] {
+            let archive = TarArchiveRef::with_block_size(data, block_size).unwrap();
+            assert_eq!(archive.entries().count(), 3);
+        }
+        for block_size in [0, 1000, 4096, 20480] {
+            assert_eq!(
+                TarArchiveRef::with_block_size(data, block_size),
+                Err(TarError::CorruptData(CorruptDataError))
+            );
+        }
+        assert_eq!(TarArchiveRef::new(data).unwrap().record_size(), None);
+
+        // The terminator at 4096 is followed by zero padding to 10240 bytes.
+        let mut data = data.to_vec();
+        data[6000] = 1;
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.trailing_data(), &data[5632..]);
+        let archive = TarArchiveRef::with_block_size(&data, 1024).unwrap();
+        assert_eq!(archive.record_size(), Some(1024));
+        assert_eq!(archive.trailing_data(), &data[5120..]);
+        assert_eq!(
+            TarArchiveRef::with_block_size(&data, 10240),
+            Err(TarError::CorruptData(CorruptDataError))
+        );
+    }
+
+    #[test]
+    fn test_header_iterator() {
+        let archive = include_bytes!("../tests/gnu_tar_default.tar");
+        let iter = ArchiveHeaderIterator::new(archive);
+        let names = iter
+            .map(|(_i, hdr)| hdr.name.as_str().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names.as_slice(),
+            &[
+                "bye_world_513b.txt",
+                "hello_world_513b.txt",
+                "hello_world.txt",
+            ]
+        )
+    }
+
+    /// The test here is that no panics occur.
+    #[test]
+    fn test_print_archive_headers() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+
+        let iter = ArchiveHeaderIterator::new(data);
+        let entries = iter.map(|(_, hdr)| hdr).collect::<Vec<_>>();
+        println!("{:#?}", entries);
+    }
+
+    /// The test here is that no panics occur.
+    #[test]
+    fn test_print_archive_list() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        println!("{:#?}", entries);
+    }
+
+    /// Tests various weird (= invalid, corrupt) tarballs that are bundled
+    /// within this file. The tarball(s) originate from a fuzzing process from a
+    /// GitHub contributor [0].
+    ///
+    /// The test succeeds if no panics occur.
+    ///
+    /// [0] https://github.com/phip1611/tar-no-std/issues/12#issuecomment-2092632090
+    #[test]
+    fn test_weird_fuzzing_tarballs() {
+        /*std::env::set_var("RUST_LOG", "trace");
+        std::env::set_var("RUST_LOG_STYLE", "always");
+        env_logger::init();*/
+
+        let main_tarball =
+            TarArchiveRef::new(include_bytes!("../tests/weird_fuzzing_tarballs.tar")).unwrap();
+
+        let mut all_entries = vec![];
+        for tarball in main_tarball.entries() {
+            let tarball = TarArchiveRef::new(tarball.data()).unwrap();
+            for entry in tarball.entries() {
+                all_entries.push(entry.filename());
+            }
+        }
+
+        // Test succeeds if this works without a panic.
+        for entry in all_entries {
+            eprintln!("\"{entry:?}\",");
+        }
+    }
+
+    /// `try_entries` reports why the iteration of a malformed archive ended.
+    #[test]
+    fn test_try_entries_errors() {
+        let main_tarball =
+            TarArchiveRef::new(include_bytes!("../tests/weird_fuzzing_tarballs.tar")).unwrap();
+        let errors = |id: &str| {
+            let tarball = main_tarball
+                .find_by(|entry| entry.filename().as_str().unwrap().contains(id))
+                .unwrap();
+            let archive = TarArchiveRef::new(tarball.data()).unwrap();
+            archive
+                .try_entries()
+                .filter_map(Result::err)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(errors("id:000000,"), []);
+        assert_eq!(
+            errors("id:000005,"),
+            [TarError::PayloadTooLarge { size: 4679 }]
+        );
+        assert_eq!(
+            errors("id:000010,"),
+            [TarError::UnparsableSize { block_index: 5 }]
+        );
+        assert_eq!(
+            errors("id:000022,"),
+            [TarError::InvalidTypeFlag {
+                block_index: 3,
+                typeflag: 0xa1
+            }]
+        );
+
+        // Headers without payload up to the end of the data.
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let mut hdr = PosixHeader::from_block(&data[..BLOCKSIZE]).unwrap();
+        hdr.typeflag = TypeFlag::DIRTYPE.into();
+        hdr.recompute_checksum();
+        let mut data = data[..4096].to_vec();
+        data.extend_from_slice(hdr.as_bytes());
+        data.extend_from_slice(hdr.as_bytes());
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.try_entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 4);
+        assert!(entries[..3].iter().all(Result::is_ok));
+        assert_eq!(
+            entries[3].as_ref().unwrap_err(),
+            &TarError::MissingTerminator
+        );
+        assert_eq!(archive.entries().count(), 3);
+    }
+
+    /// Tests to read the entries from existing archives in various Tar flavors.
+    #[test]
+    fn test_archive_entries() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_gnu.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_oldgnu.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_posix.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_v7.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_archive_content(&entries);
+    }
+
+    /// Tests to read the entries from an existing tarball with a directory in it
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_split_by_top_dir() {
+        let mut builder = crate::TarBuilder::new();
+        builder.append_file("a/1.txt", b"a1").unwrap();
+        builder.append_file("top.txt", b"top").unwrap();
+        builder.append_file("b/2.txt", b"b2").unwrap();
+        builder.append_file("a/sub/3.txt", b"a3").unwrap();
+        let archive = TarArchive::new(builder.finish()).unwrap();
+
+        let groups = archive.split_by_top_dir();
+        let names = |archive: &TarArchive| {
+            archive
+                .entries()
+                .map(|entry| (entry.filename(), entry.data_as_str().unwrap().to_owned()))
+                .map(|(name, data)| (name.as_str().unwrap().to_owned(), data))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["", "a", "b"]);
+        assert_eq!(names(&groups[""]), [("top.txt".into(), "top".into())]);
+        assert_eq!(
+            names(&groups["a"]),
+            [
+                ("a/1.txt".into(), "a1".into()),
+                ("a/sub/3.txt".into(), "a3".into())
+            ]
+        );
+        assert_eq!(names(&groups["b"]), [("b/2.txt".into(), "b2".into())]);
+        for archive in groups.values() {
+            let archive = TarArchiveRef::new(&archive.data).unwrap();
+            assert_eq!(
+                archive.terminator_offset(),
+                Some(archive.data.len() - 2 * BLOCKSIZE)
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_split_by_top_dir_long_name() {
+        let long_name = "a/".repeat(75) + "xy";
+        let mut builder = crate::TarBuilder::with_format(crate::TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        builder.append_file("b/short.txt", b"short").unwrap();
+        let archive = TarArchive::new(builder.finish()).unwrap();
+
+        let groups = archive.split_by_top_dir();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["a", "b"]);
+        let entries = groups["a"].entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename_string(), long_name);
+        assert_eq!(entries[0].data(), b"long");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_split_by_top_dir_pax_path() {
+        let path = String::from("a/") + &"x".repeat(298);
+        let records = alloc::format!("310 path={path}\n");
+        let mut builder = crate::TarBuilder::new();
+        builder
+            .append_file("PaxHeaders/file", records.as_bytes())
+            .unwrap();
+        builder.append_file("file", b"pax").unwrap();
+        let mut data = builder.finish();
+        let mut hdr = PosixHeader::from_block(&data[..BLOCKSIZE]).unwrap();
+        hdr.typeflag = TypeFlag::XHDTYPE.into();
+        hdr.recompute_checksum();
+        data[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+        let archive = TarArchive::new(data).unwrap();
+
+        let groups = archive.split_by_top_dir();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["a"]);
+        let entries = groups["a"].entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename_string(), path);
+        assert_eq!(entries[0].data(), b"pax");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_entry_ranges() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchive::new(data.to_vec().into_boxed_slice()).unwrap();
+        let ranges = archive.entry_ranges();
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].0, BLOCKSIZE..BLOCKSIZE + 513);
+        for ((range, filename), entry) in ranges.iter().zip(archive.entries()) {
+            assert_eq!(filename, &entry.filename());
+            assert_eq!(&data[range.clone()], entry.data());
+        }
+        assert!(ranges.windows(2).all(|w| w[0].0.end <= w[1].0.start));
+    }
+
+    #[test]
+    fn test_archive_with_long_dir_entries() {
+        // tarball created with:
+        //     $ cd tests; gtar --format=ustar -cf gnu_tar_ustar_long.tar 012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678 01234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234/ABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJ
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_long.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+
+        assert_eq!(entries.len(), 2);
+        // Maximum length of a directory and name when the directory itself is tar'd
+        assert_entry_content(&entries[0], "012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678/ABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJ", 7);
+        // Maximum length of a directory and name when only the file is tar'd.
+        assert_entry_content(&entries[1], "01234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234/ABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJ", 7);
+    }
+
+    #[test]
+    fn test_archive_with_deep_dir_entries() {
+        // tarball created with:
+        //     $ cd tests; gtar --format=ustar -cf gnu_tar_ustar_deep.tar 0123456789
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_deep.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+
+        assert_eq!(entries.len(), 1);
+        assert_entry_content(&entries[0], "0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/empty", 0);
+    }
+
+    #[test]
+    fn test_contiguous_file() {
+        // turn the first file into a contiguous file
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let mut hdr = PosixHeader::from_block(&data[..BLOCKSIZE]).unwrap();
+        hdr.typeflag = TypeFlag::CONTTYPE.into();
+        hdr.recompute_checksum();
+        let mut data = data.to_vec();
+        data[..BLOCKSIZE].copy_from_slice(hdr.as_bytes());
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let mut entries = archive.entries();
+        let entry = entries.next().unwrap();
+        assert_eq!(
+            entry.posix_header().typeflag.try_to_type_flag(),
+            Ok(TypeFlag::CONTTYPE)
+        );
+        assert_eq!(entry.filename().as_str(), Ok("bye_world_513b.txt"));
+        assert_eq!(entry.size(), 513);
+        assert_eq!(entries.count(), 2);
+    }
+
+    #[test]
+    fn test_header_bytes() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        for entry in archive.entries() {
+            let bytes = entry.header_bytes();
+            let offset = entry.header_block_index * BLOCKSIZE;
+            assert_eq!(bytes, &data[offset..offset + BLOCKSIZE]);
+            assert_eq!(bytes, &entry.raw_blocks()[..BLOCKSIZE]);
+
+            let hdr = PosixHeader::from_block(bytes).unwrap();
+            assert_eq!(hdr.as_bytes(), bytes);
+            assert_eq!(hdr.name, entry.posix_header().name);
+            assert_eq!(hdr.size.as_number::<u64>(), Ok(entry.size() as u64));
+            assert!(hdr.checksum_is_valid());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_filename_with_capacity() {
+        let long_name = "0123456789/".repeat(27) + "file.txt";
+        let mut builder = TarBuilder::with_format(crate::TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        builder.append_file("short.txt", b"short").unwrap();
+        let data = builder.finish();
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+
+        // The default capacity truncates the long name to the header field.
+        assert_ne!(entries[0].filename().as_str(), Ok(long_name.as_str()));
+        let name = entries[0].filename_with_capacity::<512>().unwrap();
+        assert_eq!(name.as_str(), Ok(long_name.as_str()));
+        assert_eq!(
+            entries[0].filename_with_capacity::<256>(),
+            Err(CapacityError {
+                requested: 305,
+                available: 256
+            })
+        );
+        assert_eq!(entries[0].filename_string(), long_name);
+
+        let name = entries[1].filename_with_capacity::<16>().unwrap();
+        assert_eq!(name.as_str(), Ok("short.txt"));
+        assert_eq!(entries[1].filename_string(), "short.txt");
+    }
+
+    #[test]
+    fn test_copy_data_into() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.find("hello_world.txt").unwrap();
+
+        let mut exact = [0; 12];
+        assert_eq!(entry.copy_data_into(&mut exact), Ok(12));
+        assert_eq!(&exact, b"Hello World\n");
+
+        let mut oversized = [0xff; 16];
+        assert_eq!(entry.copy_data_into(&mut oversized), Ok(12));
+        assert_eq!(&oversized[..12], b"Hello World\n");
+        assert_eq!(oversized[12..], [0xff; 4]);
+
+        let mut small = [0; 11];
+        assert_eq!(
+            entry.copy_data_into(&mut small),
+            Err(BufferTooSmallError {
+                required: 12,
+                available: 11
+            })
+        );
+        assert_eq!(small, [0; 11]);
+    }
+
+    #[test]
+    fn test_path_components() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.find("hello_world.txt").unwrap();
+        assert!(entry.path_components().eq(["hello_world.txt"]));
+
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_deep.tar")).unwrap();
+        let entry = archive.entries().next().unwrap();
+        let components = entry.path_components().collect::<Vec<_>>();
+        assert_eq!(components.len(), 13);
+        assert!(components[..12].iter().all(|dir| *dir == "0123456789"));
+        assert_eq!(components[12], "empty");
+
+        let mut entry = archive.entries().next().unwrap();
+        entry.filename = TarFormatString::try_from_str("/dirA//dirB/").unwrap();
+        assert!(entry.path_components().eq(["dirA", "dirB"]));
+        entry.filename = TarFormatString::try_from_str("").unwrap();
+        assert_eq!(entry.path_components().next(), None);
+    }
+
+    #[test]
+    fn test_default_archive_with_dir_entries() {
+        // tarball created with:
+        //     $ gtar -cf tests/gnu_tar_default_with_dir.tar --exclude '*.tar' --exclude '012345678*' tests
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default_with_dir.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+
+        assert_archive_with_dir_content(&entries);
+    }
+
+    #[test]
+    fn test_ustar_archive_with_dir_entries() {
+        // tarball created with:
+        //     $(osx) tar -cf tests/mac_tar_ustar_with_dir.tar --format=ustar --exclude '*.tar' --exclude '012345678*' tests
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/mac_tar_ustar_with_dir.tar")).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+
+        assert_archive_with_dir_content(&entries);
+    }
+
+    #[test]
+    fn test_entries_with_pax() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax.tar")).unwrap();
+        let entries = archive.entries_with_pax().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        let (entry, pax) = &entries[2];
+        assert_eq!(entry.filename().as_str(), Ok("hello_world.txt"));
+        let pax = pax.unwrap();
+        assert_eq!(pax.get("mtime"), Some(b"1633330476.680239683".as_slice()));
+        assert_eq!(pax.global_records().count(), 0);
+        let keys = pax.records().map(|r| r.key()).collect::<Vec<_>>();
+        assert_eq!(keys, ["mtime", "atime", "ctime"]);
+
+        // tarball created with:
+        //     $ mkdir pax && printf 'Hello World\n' > pax/hello_world.txt && printf 'long\n' > pax/aaa[...]aaa.txt
+        //     $ gtar --format=pax --owner=0 --group=0 --sort=name --pax-option='comment=global,delete=atime,delete=ctime' -cf tests/gnu_tar_pax_global.tar pax
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax_global.tar")).unwrap();
+        let entries = archive.entries_with_pax().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        let long_name = std::format!("pax/{}.txt", "a".repeat(120));
+        let pax = entries[0].1.unwrap();
+        assert_eq!(pax.get("path"), Some(long_name.as_bytes()));
+        assert_eq!(entries[0].0.filename().as_str(), Ok(long_name.as_str()));
+        assert_eq!(entries[0].0.pax(), Some(pax));
+        assert_eq!(entries[0].0.data(), b"long\n");
+        assert_eq!(pax.get("comment"), Some(b"global".as_slice()));
+        assert_eq!(entries[1].0.filename().as_str(), Ok("pax/hello_world.txt"));
+        let pax = entries[1].1.unwrap();
+        assert_eq!(pax.get("path"), None);
+        assert_eq!(pax.get("comment"), Some(b"global".as_slice()));
+
+        // archives without extended headers
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        assert!(archive.entries_with_pax().all(|(_, pax)| pax.is_none()));
+    }
+
+    /// The `size` record of a PAX extended header overrides the size field.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_pax_size_override() {
+        let mut builder = crate::TarBuilder::new();
+        builder
+            .append_file("PaxHeaders/file", b"10 size=3\n12 uid=1000\n")
+            .unwrap();
+        builder.append_file("file", b"abc").unwrap();
+        builder.append_file("next", b"next").unwrap();
+        let mut data = builder.finish();
+        let mut patch = |block_index: usize, patch: &dyn Fn(&mut PosixHeader)| {
+            let block = &mut data[block_index * BLOCKSIZE..][..BLOCKSIZE];
+            let mut hdr = PosixHeader::from_block(block).unwrap();
+            patch(&mut hdr);
+            hdr.recompute_checksum();
+            block.copy_from_slice(hdr.as_bytes());
+        };
+        patch(0, &|hdr| hdr.typeflag = TypeFlag::XHDTYPE.into());
+        patch(2, &|hdr| hdr.size = TarFormatOctal::new(*b"00000000000\0"));
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].filename().as_str(), Ok("file"));
+        assert_eq!(entries[0].data(), b"abc");
+        assert_eq!(entries[0].uid(), Some(1000));
+        assert_eq!(entries[0].gid(), Some(0));
+        assert_eq!(entries[1].filename().as_str(), Ok("next"));
+        assert_eq!(entries[1].data(), b"next");
+        assert_eq!(entries[1].uid(), Some(0));
+
+        let mut toc = [TocEntry::default(); 2];
+        assert_eq!(archive.toc_into(&mut toc), 2);
+        assert_eq!(archive.entry_at(&toc[0]).unwrap().data(), b"abc");
+        assert_eq!(archive.entry_at(&toc[1]).unwrap().data(), b"next");
+    }
+
+    /// GNU long name records (typeflag `L`) override the name of the next
+    /// entry.
+    #[test]
+    fn test_gnu_long_name() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_gnu_long.tar")).unwrap();
+        let long_name = "b".repeat(60) + "/" + &"c".repeat(60);
+        let mut entries = archive.entries();
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.filename().as_str(), Ok(long_name.as_str()));
+        assert_eq!(entry.long_name(), Some(long_name.as_bytes()));
+        assert_eq!(entry.data(), b"Hello World\n");
+        assert!(entries.next().is_none());
+        assert_eq!(entries.skipped_count(), 2);
+    }
+
+    #[test]
+    fn test_logical_entries() {
+        let data = include_bytes!("../tests/gnu_tar_gnu_long.tar");
+        let long_name = "b".repeat(60) + "/" + &"c".repeat(60);
+        let link_name = "link_".to_string() + &"d".repeat(100);
+        let mut entries = ArchiveHeaderIterator::new(data).logical_entries();
+
+        let dir = entries.next().unwrap();
+        assert_eq!(dir.typeflag().try_to_type_flag(), Ok(TypeFlag::DIRTYPE));
+        assert_eq!(dir.long_name(), None);
+        assert_eq!(dir.size(), 0);
+
+        // The `L` record is folded into the header of the file.
+        let file = entries.next().unwrap();
+        assert_eq!(file.typeflag().try_to_type_flag(), Ok(TypeFlag::REGTYPE));
+        assert_eq!(file.long_name(), Some(long_name.as_bytes()));
+        assert_eq!(file.filename().as_str(), Ok(long_name.as_str()));
+        assert_eq!(file.data(), b"Hello World\n");
+        assert!(file.pax().is_none());
+
+        let link = entries.next().unwrap();
+        assert_eq!(link.typeflag().try_to_type_flag(), Ok(TypeFlag::SYMTYPE));
+        assert_eq!(link.long_name(), Some(link_name.as_bytes()));
+        assert_eq!(link.long_link_target(), Some(long_name.as_bytes()));
+        assert!(link.data().is_empty());
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn test_data_aligned() {
+        #[repr(C, align(512))]
+        struct Aligned([u8; 10240]);
+        let data = Aligned(*include_bytes!("../tests/gnu_tar_default.tar"));
+        let archive = TarArchiveRef::new(&data.0).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+
+        // 513 bytes
+        assert_eq!(entries[0].data_aligned::<u8>().map(<[u8]>::len), Some(513));
+        assert_eq!(entries[0].data_aligned::<u32>(), None);
+
+        // "Hello World\n"
+        let words = entries[2].data_aligned::<[u8; 4]>().unwrap();
+        assert_eq!(words, &[*b"Hell", *b"o Wo", *b"rld\n"]);
+        let words = entries[2].data_aligned::<u32>().unwrap();
+        assert_eq!(words[0], u32::from_ne_bytes(*b"Hell"));
+        assert_eq!(entries[2].data_aligned::<u64>(), None);
+
+        // misaligned archive
+        let mut misaligned = std::vec![0; 10240 + 1];
+        misaligned[1..].copy_from_slice(&data.0);
+        let archive = TarArchiveRef::new(&misaligned[1..]).unwrap();
+        let entry = archive.entries().nth(2).unwrap();
+        assert!(entry.data_aligned::<u8>().is_some());
+        assert_eq!(entry.data_aligned::<u32>(), None);
+    }
+
+    #[test]
+    fn test_payload_bytes() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let payloads = archive.payload_bytes().collect::<Vec<_>>();
+        assert_eq!(payloads.len(), 3);
+        for (payload, entry) in payloads.iter().zip(archive.entries()) {
+            assert_eq!(*payload, entry.data());
+        }
+        assert_eq!(payloads[2], b"Hello World\n");
+        assert_eq!(
+            archive.payload_bytes().map(<[u8]>::len).sum::<usize>(),
+            513 + 513 + 12
+        );
+    }
 
-        #[cfg(feature = "alloc")]
-        {
-            assert_eq!(TarArchive::new(vec![].into_boxed_slice()), Err(CorruptDataError));
-            assert_eq!(TarArchive::new(vec![0].into_boxed_slice()), Err(CorruptDataError));
-            assert!(TarArchive::new(vec![0; BLOCKSIZE * MIN_BLOCK_COUNT].into_boxed_slice()).is_ok());
+    #[test]
+    fn test_raw_blocks() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries[0].raw_blocks(), &data[..3 * BLOCKSIZE]);
+        assert_eq!(entries[2].raw_blocks().len(), 2 * BLOCKSIZE);
+
+        // sub-archive with the first and the last file
+        let mut sub_archive = Vec::new();
+        sub_archive.extend_from_slice(entries[0].raw_blocks());
+        sub_archive.extend_from_slice(entries[2].raw_blocks());
+        sub_archive.extend_from_slice(&[0; 2 * BLOCKSIZE]);
+        let sub_archive = TarArchiveRef::new(&sub_archive).unwrap();
+        let sub_entries = sub_archive.entries().collect::<Vec<_>>();
+        assert_eq!(sub_entries.len(), 2);
+        assert_eq!(sub_entries[0].filename(), entries[0].filename());
+        assert_eq!(sub_entries[0].data(), entries[0].data());
+        assert_eq!(sub_entries[1].filename(), entries[2].filename());
+        assert_eq!(sub_entries[1].data(), entries[2].data());
+        assert_eq!(
+            entries[0].raw_blocks_with_extensions(),
+            entries[0].raw_blocks()
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_raw_blocks_with_extensions() {
+        let long_name = "a/".repeat(75) + "xy";
+        let mut builder = crate::TarBuilder::with_format(crate::TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        let data = builder.finish();
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entry = archive.entries().next().unwrap();
+        // the header and the payload of the GNU long name record
+        assert_eq!(entry.raw_blocks_with_extensions(), &data[..4 * BLOCKSIZE]);
+        assert_eq!(entry.raw_blocks(), &data[2 * BLOCKSIZE..4 * BLOCKSIZE]);
+        assert_eq!(entry.data_reader().remaining_bytes(), 4);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax.tar")).unwrap();
+        for entry in archive.entries() {
+            let extensions = entry.raw_blocks_with_extensions();
+            assert!(extensions.len() > entry.raw_blocks().len());
+            assert!(extensions.ends_with(entry.raw_blocks()));
+            let hdr = PosixHeader::from_block(&extensions[..BLOCKSIZE]).unwrap();
+            assert_eq!(hdr.typeflag.try_to_type_flag(), Ok(TypeFlag::XHDTYPE));
+        }
+    }
+
+    #[test]
+    fn test_toc() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let mut toc = [TocEntry::default(); 4];
+        assert_eq!(archive.toc_into(&mut toc), 3);
+        assert_eq!(toc[0].header_offset(), 0);
+        assert_eq!(toc[0].data_offset(), BLOCKSIZE);
+        assert_eq!(toc[0].size(), 513);
+        assert_eq!(toc[3], TocEntry::default());
+
+        for (toc_entry, entry) in toc.iter().zip(archive.entries()) {
+            let entry_at = archive.entry_at(toc_entry).unwrap();
+            assert_eq!(entry_at.filename(), entry.filename());
+            assert_eq!(entry_at.data(), entry.data());
+        }
+        assert!(archive.entry_at(&toc[3]).is_none());
+
+        // slice too small
+        let mut toc = [TocEntry::default(); 2];
+        assert_eq!(archive.toc_into(&mut toc), 2);
+        assert_eq!(
+            archive.entry_at(&toc[1]).unwrap().filename().as_str(),
+            Ok("hello_world_513b.txt")
+        );
+
+        // invalid locations
+        let invalid = |header_offset, size| TocEntry {
+            header_offset,
+            size,
+            state: ParserState::new(),
         };
+        assert!(archive.entry_at(&invalid(1, 513)).is_none());
+        assert!(archive.entry_at(&invalid(BLOCKSIZE, 513)).is_none());
+        assert!(archive.entry_at(&invalid(0, 512)).is_none());
+        assert!(archive.entry_at(&invalid(4096, 0)).is_none());
+        assert!(archive.entry_at(&invalid(1 << 20, 0)).is_none());
     }
 
     #[test]
-    fn test_header_iterator() {
-        let archive = include_bytes!("../tests/gnu_tar_default.tar");
-        let iter = ArchiveHeaderIterator::new(archive);
-        let names = iter
-            .map(|(_i, hdr)| hdr.name.as_str().unwrap())
-            .collect::<Vec<_>>();
+    fn test_toc_extension_headers() {
+        for data in [
+            &include_bytes!("../tests/gnu_tar_gnu_long.tar")[..],
+            include_bytes!("../tests/gnu_tar_pax.tar"),
+        ] {
+            let archive = TarArchiveRef::new(data).unwrap();
+            let mut toc = [TocEntry::default(); 8];
+            let count = archive.toc_into(&mut toc);
+            assert_eq!(count, archive.entries().count());
+            for (toc_entry, entry) in toc[..count].iter().zip(archive.entries()) {
+                let entry_at = archive.entry_at(toc_entry).unwrap();
+                assert_eq!(entry_at.filename(), entry.filename());
+                assert_eq!(entry_at.long_name(), entry.long_name());
+                assert_eq!(entry_at.pax, entry.pax);
+                assert_eq!(entry_at.data(), entry.data());
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_entries_payload_too_large() {
+        let mut data = include_bytes!("../tests/gnu_tar_default.tar").to_vec();
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert!(archive.try_entries().all(|entry| entry.is_ok()));
+        assert_eq!(archive.try_entries().count(), 3);
 
+        // Declare a size of 6 GiB for the second file. The size is compared
+        // as u64, so this is the same code path as on a 32-bit system, where
+        // the size can't be represented as usize.
+        let hdr = 3 * BLOCKSIZE;
+        data[hdr + 124..hdr + 136].copy_from_slice(b"60000000000\0");
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.try_entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_ok());
         assert_eq!(
-            names.as_slice(),
-            &[
-                "bye_world_513b.txt",
-                "hello_world_513b.txt",
-                "hello_world.txt",
-            ]
-        )
+            entries[1].as_ref().unwrap_err(),
+            &TarError::PayloadTooLarge { size: 6 << 30 }
+        );
+        // The infallible iterator ends at the broken entry.
+        assert_eq!(archive.entries().count(), 1);
     }
 
-    /// The test here is that no panics occur.
+    /// Padding and leftover data after the terminator are ignored.
     #[test]
-    fn test_print_archive_headers() {
+    fn test_trailing_data_after_terminator() {
         let data = include_bytes!("../tests/gnu_tar_default.tar");
+        for trailer in [[0; BLOCKSIZE], [0xff; BLOCKSIZE]] {
+            let mut data = data.to_vec();
+            for _ in 0..18 {
+                data.extend_from_slice(&trailer);
+            }
+            let archive = TarArchiveRef::new(&data).unwrap();
+            assert_eq!(archive.entries().count(), 3);
+            assert!(archive.try_entries().all(|entry| entry.is_ok()));
+        }
+    }
 
-        let iter = ArchiveHeaderIterator::new(data);
-        let entries = iter.map(|(_, hdr)| hdr).collect::<Vec<_>>();
-        println!("{:#?}", entries);
+    /// A single zero block followed by other data ends the iteration.
+    #[test]
+    fn test_lone_zero_block() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let mut data = data[..4096].to_vec();
+        data.extend_from_slice(&[0; BLOCKSIZE]);
+        data.extend_from_slice(&include_bytes!("../tests/gnu_tar_default.tar")[..4096]);
+        data.extend_from_slice(&[0; 2 * BLOCKSIZE]);
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.entries().count(), 3);
+        assert!(archive.try_entries().all(|entry| entry.is_ok()));
     }
 
-    /// The test here is that no panics occur.
     #[test]
-    fn test_print_archive_list() {
+    fn test_content_size() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        assert_eq!(data.len(), 10240);
+        assert_eq!(archive.terminator_offset(), Some(4096));
+        assert_eq!(archive.content_size(), 4096);
+
+        // the trimmed archive with the terminator is still valid
+        let trimmed = &data[..archive.content_size() + 2 * BLOCKSIZE];
+        let trimmed = TarArchiveRef::new(trimmed).unwrap();
+        assert_eq!(trimmed.entries().count(), archive.entries().count());
+
+        // no terminator
+        let data = &data[..archive.content_size()];
+        let archive = TarArchiveRef::new(data).unwrap();
+        assert_eq!(archive.terminator_offset(), None);
+        assert_eq!(archive.content_size(), data.len());
+    }
+
+    #[test]
+    fn test_inspect() {
         let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
-        println!("{:#?}", entries);
+        let stats = archive.inspect();
+        assert_eq!(stats.get(TypeFlag::REGTYPE), 3);
+        assert_eq!(stats.counts_by_type().iter().sum::<usize>(), 3);
+        assert_eq!(stats.invalid_count(), 0);
+
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_pax.tar")).unwrap();
+        let stats = archive.inspect();
+        assert_eq!(stats.get(TypeFlag::REGTYPE), 3);
+        assert_eq!(stats.get(TypeFlag::XHDTYPE), 3);
+        assert_eq!(stats.get(TypeFlag::DIRTYPE), 0);
     }
 
-    /// Tests various weird (= invalid, corrupt) tarballs that are bundled
-    /// within this file. The tarball(s) originate from a fuzzing process from a
-    /// GitHub contributor [0].
-    ///
-    /// The test succeeds if no panics occur.
-    ///
-    /// [0] https://github.com/phip1611/tar-no-std/issues/12#issuecomment-2092632090
     #[test]
-    fn test_weird_fuzzing_tarballs() {
-        /*std::env::set_var("RUST_LOG", "trace");
-        std::env::set_var("RUST_LOG_STYLE", "always");
-        env_logger::init();*/
+    fn test_trailing_data() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        // only zero padding
+        assert_eq!(archive.trailing_data(), b"");
 
-        let main_tarball =
-            TarArchiveRef::new(include_bytes!("../tests/weird_fuzzing_tarballs.tar")).unwrap();
+        let mut signed = data.to_vec();
+        let signature = [0xab; BLOCKSIZE];
+        signed.extend_from_slice(&signature);
+        let archive = TarArchiveRef::new(&signed).unwrap();
+        assert_eq!(archive.trailing_data(), signature);
+        assert_eq!(archive.entries().count(), 3);
 
-        let mut all_entries = vec![];
-        for tarball in main_tarball.entries() {
-            let tarball = TarArchiveRef::new(tarball.data()).unwrap();
-            for entry in tarball.entries() {
-                all_entries.push(entry.filename());
-            }
+        // directly after the terminator
+        signed.drain(4096 + 2 * BLOCKSIZE..data.len());
+        let archive = TarArchiveRef::new(&signed).unwrap();
+        assert_eq!(archive.trailing_data(), signature);
+
+        // no terminator
+        let archive = TarArchiveRef::new(&data[..4096]).unwrap();
+        assert_eq!(archive.trailing_data(), b"");
+    }
+
+    #[test]
+    fn test_format() {
+        let format = |data| TarArchiveRef::new(data).unwrap().format();
+        let fixtures: [(&[u8], _); 17] = [
+            (
+                include_bytes!("../tests/gnu_tar_default.tar"),
+                ArchiveFormat::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_default_with_dir.tar"),
+                ArchiveFormat::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_gnu.tar"),
+                ArchiveFormat::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_gnu_long.tar"),
+                ArchiveFormat::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_multivolume_last.tar"),
+                ArchiveFormat::V7,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_oldgnu.tar"),
+                ArchiveFormat::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_pax.tar"),
+                ArchiveFormat::Pax,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_pax_global.tar"),
+                ArchiveFormat::Pax,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_posix.tar"),
+                ArchiveFormat::Pax,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_ustar.tar"),
+                ArchiveFormat::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_ustar_deep.tar"),
+                ArchiveFormat::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_ustar_links.tar"),
+                ArchiveFormat::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_ustar_long.tar"),
+                ArchiveFormat::Ustar,
+            ),
+            (include_bytes!("../tests/gnu_tar_v7.tar"), ArchiveFormat::V7),
+            (
+                include_bytes!("../tests/mac_tar_ustar_with_dir.tar"),
+                ArchiveFormat::Ustar,
+            ),
+            (
+                include_bytes!("../tests/tar_content_subset_for_recreating_tar_files.tar"),
+                ArchiveFormat::Ustar,
+            ),
+            (
+                include_bytes!("../tests/weird_fuzzing_tarballs.tar"),
+                ArchiveFormat::Gnu,
+            ),
+        ];
+        for (data, expected) in fixtures {
+            assert_eq!(format(data), expected);
         }
+    }
 
-        // Test succeeds if this works without a panic.
-        for entry in all_entries {
-            eprintln!("\"{entry:?}\",");
+    #[test]
+    fn test_uses_extensions() {
+        let extensions = |data| TarArchiveRef::new(data).unwrap().uses_extensions();
+        assert_eq!(
+            extensions(include_bytes!("../tests/gnu_tar_ustar.tar")),
+            ExtensionFlags::empty()
+        );
+        assert_eq!(
+            extensions(include_bytes!("../tests/gnu_tar_gnu.tar")),
+            ExtensionFlags::GnuMagic
+        );
+        assert_eq!(
+            extensions(include_bytes!("../tests/gnu_tar_pax.tar")),
+            ExtensionFlags::PaxExtendedHeader
+        );
+        assert_eq!(
+            extensions(include_bytes!("../tests/gnu_tar_pax_global.tar")),
+            ExtensionFlags::PaxExtendedHeader | ExtensionFlags::PaxGlobalHeader
+        );
+        assert_eq!(
+            extensions(include_bytes!("../tests/gnu_tar_gnu_long.tar")),
+            ExtensionFlags::GnuLongName | ExtensionFlags::GnuLongLink | ExtensionFlags::GnuMagic
+        );
+    }
+
+    #[test]
+    fn test_is_safe_path() {
+        let hdr = *ArchiveHeaderIterator::new(include_bytes!("../tests/gnu_tar_ustar.tar"))
+            .next()
+            .unwrap()
+            .1;
+        let entry = |name: &str| {
+            ArchiveEntry::new(
+                TarFormatString::try_from_str(name).unwrap(),
+                &[],
+                &hdr,
+                0,
+                &[],
+            )
+        };
+        assert!(entry("bye_world_513b.txt").is_safe_path());
+        assert!(entry("./dir/file..txt").is_safe_path());
+        assert!(!entry("/etc/passwd").is_safe_path());
+        assert!(!entry("dir/../../etc/passwd").is_safe_path());
+        assert!(!entry("..").is_safe_path());
+
+        #[cfg(feature = "std")]
+        {
+            use std::path::Path;
+            assert_eq!(
+                entry("./dir//file.txt").filename_os().as_deref(),
+                Some(Path::new("dir").join("file.txt").as_path())
+            );
+            assert_eq!(entry("/etc/passwd").filename_os(), None);
+            assert_eq!(entry("dir/../../etc/passwd").filename_os(), None);
         }
     }
 
-    /// Tests to read the entries from existing archives in various Tar flavors.
+    #[cfg(feature = "alloc")]
     #[test]
-    fn test_archive_entries() {
+    fn test_check_names() {
+        let check = |names: &[&str]| {
+            let mut builder = crate::TarBuilder::new();
+            for name in names {
+                builder.append_file(name, b"x").unwrap();
+            }
+            let data = builder.finish();
+            TarArchiveRef::new(&data).unwrap().check_names()
+        };
+        let check_result = |has_duplicates, is_sorted| NameCheck {
+            has_duplicates,
+            is_sorted,
+        };
+        assert_eq!(check(&[]), check_result(false, true));
+        assert_eq!(check(&["a", "b/c", "b/d"]), check_result(false, true));
+        assert_eq!(check(&["a", "a", "b"]), check_result(true, true));
+        assert_eq!(check(&["b", "a"]), check_result(false, false));
+        assert_eq!(check(&["b", "a", "a"]), check_result(true, false));
+
         let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);
+        assert_eq!(archive.check_names(), check_result(false, false));
+    }
 
-        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_gnu.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);
+    /// Empty files end exactly at the next header or the terminating zero
+    /// blocks.
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_check_names_empty_files() {
+        let mut builder = crate::TarBuilder::new();
+        for name in ["a", "a", "b"] {
+            builder.append_file(name, b"").unwrap();
+        }
+        let data = builder.finish();
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.entries().count(), 3);
+        assert_eq!(
+            archive.check_names(),
+            NameCheck {
+                has_duplicates: true,
+                is_sorted: true,
+            }
+        );
+    }
 
-        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_oldgnu.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);
+    #[test]
+    fn test_directory_size_limit() {
+        let archive = include_bytes!("../tests/gnu_tar_default_with_dir.tar");
+        let mut headers = ArchiveHeaderIterator::new(archive);
+        let (block_index, hdr) = headers
+            .find(|(_, hdr)| hdr.typeflag.try_to_type_flag() == Ok(TypeFlag::DIRTYPE))
+            .unwrap();
+        let mut hdr = *hdr;
+        let entry = ArchiveEntry::new(filename_from_header(&hdr), &[], &hdr, block_index, &[]);
+        assert_eq!(entry.directory_size_limit(), Some(0));
 
-        // UNSUPPORTED. Uses extensions.
-        /*let archive = TarArchive::new(include_bytes!("../tests/gnu_tar_pax.tar"));
-        let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);*/
+        hdr.size = TarFormatOctal::new(*b"00000001000\0");
+        let entry = ArchiveEntry::new(filename_from_header(&hdr), &[], &hdr, block_index, &[]);
+        assert_eq!(entry.directory_size_limit(), Some(512));
 
-        // UNSUPPORTED. Uses extensions.
-        /*let archive = TarArchive::new(include_bytes!("../tests/gnu_tar_posix.tar"));
-        let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);*/
+        let archive = TarArchiveRef::new(archive).unwrap();
+        assert!(archive
+            .entries()
+            .all(|entry| entry.directory_size_limit().is_none()));
+    }
 
-        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);
+    #[test]
+    fn test_volume_offset() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        assert!(archive
+            .entries()
+            .all(|entry| entry.volume_offset().is_none()));
 
-        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_v7.tar")).unwrap();
+        // last volume of `tar -c -M -L 10 -f vol1.tar -f vol2.tar -f vol3.tar`
+        let archive =
+            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_multivolume_last.tar")).unwrap();
         let entries = archive.entries().collect::<Vec<_>>();
-        assert_archive_content(&entries);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename().as_str(), Ok("big.txt"));
+        assert_eq!(entries[0].volume_offset(), Some(19456));
+        assert_eq!(entries[0].size(), 2544);
+        assert!(entries[0].data_as_str().unwrap().ends_with("line 01999\n"));
     }
 
-    /// Tests to read the entries from an existing tarball with a directory in it
     #[test]
-    fn test_archive_with_long_dir_entries() {
-        // tarball created with:
-        //     $ cd tests; gtar --format=ustar -cf gnu_tar_ustar_long.tar 012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678 01234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234/ABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJ
-        let archive =
-            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_long.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
+    fn test_display() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let entry = archive.find("hello_world.txt").unwrap();
+        assert_eq!(
+            std::format!("{entry}"),
+            "-rw-r--r-- pschuster/pschuster 12 2021-10-04 06:54 hello_world.txt"
+        );
+    }
 
-        assert_eq!(entries.len(), 2);
-        // Maximum length of a directory and name when the directory itself is tar'd
-        assert_entry_content(&entries[0], "012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678/ABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJ", 7);
-        // Maximum length of a directory and name when only the file is tar'd.
-        assert_entry_content(&entries[1], "01234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234/ABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJABCDEFGHIJ", 7);
+    #[test]
+    fn test_long_listing_line() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let mut lines = archive.entries().map(|entry| {
+            let mut line = String::new();
+            entry.long_listing_line(&mut line).unwrap();
+            line
+        });
+        assert_eq!(
+            lines.next().unwrap(),
+            "-rw-rw-r-- pschuster/pschuster 513 2021-10-04 06:54 bye_world_513b.txt"
+        );
+        assert_eq!(
+            lines.nth(1).unwrap(),
+            "-rw-r--r-- pschuster/pschuster 12 2021-10-04 06:54 hello_world.txt"
+        );
+
+        let mut hdr = *archive.entries().next().unwrap().posix_header();
+        hdr.uname = TarFormatString::new([0; 32]);
+        hdr.gname = TarFormatString::new([0; 32]);
+        hdr.mode = ModeFlags::from_bits(0o4755).unwrap().into();
+        hdr.typeflag = TypeFlag::DIRTYPE.into();
+        hdr.mtime = TarFormatOctal::from_number(951_782_400).unwrap();
+        let entry = ArchiveEntry::new(TarFormatString::new([b'a'; 256]), &[], &hdr, 0, &[]);
+        let mut line = String::new();
+        entry.long_listing_line(&mut line).unwrap();
+        assert_eq!(
+            line,
+            std::format!(
+                "drwsr-xr-x 1001/1001         0 2000-02-29 00:00 {}",
+                "a".repeat(256)
+            )
+        );
+
+        // The PAX record takes precedence.
+        let mut entry = entry;
+        entry.pax = PaxHeader::new(Some(b"17 mtime=86400.5\n"), None);
+        let mut line = String::new();
+        entry.long_listing_line(&mut line).unwrap();
+        assert!(line.contains(" 1970-01-02 00:00 "));
     }
 
     #[test]
-    fn test_archive_with_deep_dir_entries() {
-        // tarball created with:
-        //     $ cd tests; gtar --format=ustar -cf gnu_tar_ustar_deep.tar 0123456789
-        let archive =
-            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_ustar_deep.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
+    fn test_device_numbers() {
+        let archive = include_bytes!("../tests/gnu_tar_ustar.tar");
+        let mut hdr = *ArchiveHeaderIterator::new(archive).next().unwrap().1;
+        // not applicable to regular files
+        assert_eq!(device_numbers(&hdr, false), None);
+        assert_eq!(device_numbers(&hdr, true), None);
 
-        assert_eq!(entries.len(), 1);
-        assert_entry_content(&entries[0], "0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/0123456789/empty", 0);
+        hdr.typeflag = TypeFlag::CHRTYPE.into();
+        assert_eq!(device_numbers(&hdr, false), Some((0, 0)));
+        assert_eq!(device_numbers(&hdr, true), Some((0, 0)));
+
+        // empty fields, as written by GNU Tar for regular files
+        hdr.typeflag = TypeFlag::BLKTYPE.into();
+        hdr.dev_major = TarFormatOctal::new([0; 8]);
+        hdr.dev_minor = TarFormatOctal::new(*b"000010 \0");
+        assert_eq!(device_numbers(&hdr, false), Some((0, 8)));
+        assert_eq!(device_numbers(&hdr, true), Some((0, 8)));
+
+        // hex, as written by some nonstandard tools
+        hdr.dev_major = TarFormatOctal::new(*b"1a\0\0\0\0\0\0");
+        hdr.dev_minor = TarFormatOctal::new(*b"ff\0\0\0\0\0\0");
+        assert_eq!(device_numbers(&hdr, false), None);
+        assert_eq!(device_numbers(&hdr, true), Some((0x1a, 0xff)));
+
+        hdr.dev_minor = TarFormatOctal::new(*b"xyz\0\0\0\0\0");
+        assert_eq!(device_numbers(&hdr, true), None);
     }
 
     #[test]
-    fn test_default_archive_with_dir_entries() {
-        // tarball created with:
-        //     $ gtar -cf tests/gnu_tar_default_with_dir.tar --exclude '*.tar' --exclude '012345678*' tests
-        let archive =
-            TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default_with_dir.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
+    fn test_device_entries() {
+        // $ mknod dev/loop0 b 7 0 && mknod dev/null c 1 3
+        let data = include_bytes!("../tests/gnu_tar_ustar_devices.tar");
+        let archive = TarArchiveRef::new(data).unwrap();
+        let devices = archive
+            .raw_entries()
+            .map(|entry| (entry.typeflag(), entry.device_numbers()))
+            .collect::<Vec<_>>();
+        let expected = [
+            (TypeFlag::DIRTYPE, None),
+            (TypeFlag::BLKTYPE, Some((7, 0))),
+            (TypeFlag::CHRTYPE, Some((1, 3))),
+        ];
+        assert_eq!(devices, expected);
 
-        assert_archive_with_dir_content(&entries);
+        let devices = ArchiveHeaderIterator::new(data)
+            .logical_entries()
+            .map(|entry| entry.device_numbers_lenient())
+            .collect::<Vec<_>>();
+        assert_eq!(devices, [None, Some((7, 0)), Some((1, 3))]);
+        // devices are no files
+        assert_eq!(archive.entries().count(), 0);
     }
 
+    #[cfg(feature = "crc")]
     #[test]
-    fn test_ustar_archive_with_dir_entries() {
-        // tarball created with:
-        //     $(osx) tar -cf tests/mac_tar_ustar_with_dir.tar --format=ustar --exclude '*.tar' --exclude '012345678*' tests
-        let archive =
-            TarArchiveRef::new(include_bytes!("../tests/mac_tar_ustar_with_dir.tar")).unwrap();
-        let entries = archive.entries().collect::<Vec<_>>();
+    fn test_payload_crc32() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let crcs = archive
+            .entries()
+            .map(|entry| entry.payload_crc32())
+            .collect::<Vec<_>>();
+        // $ crc32 tests/bye_world_513b.txt tests/hello_world.txt
+        assert_eq!(crcs[0], 0x1243_f5af);
+        assert_eq!(crcs[2], 0xb095_e5e3);
+    }
 
-        assert_archive_with_dir_content(&entries);
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_sha256() {
+        let archive = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        // $ sha256sum tests/gnu_tar_default.tar
+        assert_eq!(
+            archive.sha256(),
+            hex_literal("23dc9dd9bbfce9bdb0dc7b0d48875a3eed6558929f345d3143deb9bac6f05cd0")
+        );
+
+        let entry = archive.entries().nth(2).unwrap();
+        assert_eq!(entry.filename().as_str(), Ok("hello_world.txt"));
+        // $ sha256sum tests/hello_world.txt
+        assert_eq!(
+            entry.sha256(),
+            hex_literal("d2a84f4b8b650937ec8f73cd8be2c74add5a911ba64df27458ed8229da804a26")
+        );
+    }
+
+    /// Parses a hex string into a 32 byte array.
+    #[cfg(feature = "sha2")]
+    fn hex_literal(hex: &str) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        bytes
     }
 
     /// Like [`test_archive_entries`] but with additional `alloc` functionality.
@@ -535,6 +5699,121 @@ mod tests {
         assert_eq!(data, archive.into());
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_file_follows_links() {
+        // tarball created with:
+        //     $ mkdir -p links/dir && printf 'Hello World\n' > links/hello_world.txt
+        //     $ ln -s hello_world.txt links/symlink && ln links/hello_world.txt links/hardlink
+        //     $ ln -s ../symlink links/dir/relative_symlink && ln -s self_loop links/self_loop
+        //     $ ln -s loop_b links/loop_a && ln -s loop_a links/loop_b
+        //     $ gtar --format=ustar --owner=0 --group=0 --sort=name -cf tests/gnu_tar_ustar_links.tar links
+        let data = include_bytes!("../tests/gnu_tar_ustar_links.tar")
+            .to_vec()
+            .into_boxed_slice();
+        let archive = TarArchive::new(data).unwrap();
+        let expected = Some(b"Hello World\n".as_slice());
+
+        // regular file (archived first due to the sorting)
+        assert_eq!(archive.read_file("links/hardlink").as_deref(), expected);
+        assert_eq!(archive.read_file("./links//hardlink").as_deref(), expected);
+        // hardlink to "links/hardlink"
+        assert_eq!(
+            archive.read_file("links/hello_world.txt").as_deref(),
+            expected
+        );
+        // symlink to hardlink to regular file
+        assert_eq!(archive.read_file("links/symlink").as_deref(), expected);
+        // relative symlink to symlink to hardlink to regular file
+        assert_eq!(
+            archive.read_file("links/dir/relative_symlink").as_deref(),
+            expected
+        );
+
+        // link cycles
+        assert_eq!(archive.read_file("links/self_loop"), None);
+        assert_eq!(archive.read_file("links/loop_a"), None);
+        assert_eq!(archive.read_file("links/loop_b"), None);
+
+        // not a regular file
+        assert_eq!(archive.read_file("links/dir"), None);
+        // doesn't exist
+        assert_eq!(archive.read_file("links/missing"), None);
+    }
+
+    /// Sets the typeflag and the link name of the headers at the given block
+    /// indices.
+    #[cfg(feature = "alloc")]
+    fn patch_links(data: &mut [u8], patches: &[(usize, TypeFlag, &str)]) {
+        for (block_index, typeflag, linkname) in patches {
+            let block = &mut data[block_index * BLOCKSIZE..][..BLOCKSIZE];
+            let mut hdr = PosixHeader::from_block(block).unwrap();
+            hdr.typeflag = (*typeflag).into();
+            hdr.linkname = TarFormatString::try_from_str(linkname).unwrap();
+            hdr.recompute_checksum();
+            block.copy_from_slice(hdr.as_bytes());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_file_absolute_symlink() {
+        let mut builder = crate::TarBuilder::new();
+        builder.append_file("etc/f", b"absolute").unwrap();
+        builder.append_file("a/etc/f", b"relative").unwrap();
+        builder.append_file("a/link", b"").unwrap();
+        let mut data = builder.finish();
+        patch_links(&mut data, &[(4, TypeFlag::SYMTYPE, "/etc/f")]);
+        let archive = TarArchive::new(data).unwrap();
+        assert_eq!(
+            archive.read_file("a/link").as_deref(),
+            Some(b"absolute".as_slice())
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_read_file_long_names() {
+        let target = "d/".repeat(60) + "target.txt";
+        let link = "l/".repeat(60) + "hardlink";
+        let pax = |key: &str, value: &str| {
+            let len = key.len() + value.len() + 3;
+            let len = len + alloc::format!("{}", len + 2).len();
+            alloc::format!("{len} {key}={value}\n")
+        };
+        let mut builder = crate::TarBuilder::with_format(crate::TarFormat::Gnu);
+        // blocks 0 and 1: GNU long name, blocks 2 and 3: file
+        builder.append_file(&target, b"long").unwrap();
+        // blocks 4 and 5: PAX extended header, block 6: hardlink
+        let records = pax("path", &link) + &pax("linkpath", &target);
+        builder
+            .append_file("PaxHeaders/hardlink", records.as_bytes())
+            .unwrap();
+        builder.append_file("hardlink", b"").unwrap();
+        // blocks 7 and 8: PAX extended header, block 9: symlink
+        let records = pax("linkpath", &(String::from("/") + &target));
+        builder
+            .append_file("PaxHeaders/symlink", records.as_bytes())
+            .unwrap();
+        builder.append_file("symlink", b"").unwrap();
+        let mut data = builder.finish();
+        patch_links(
+            &mut data,
+            &[
+                (4, TypeFlag::XHDTYPE, ""),
+                (6, TypeFlag::LINK, "truncated"),
+                (7, TypeFlag::XHDTYPE, ""),
+                (9, TypeFlag::SYMTYPE, "truncated"),
+            ],
+        );
+        let archive = TarArchive::new(data).unwrap();
+        let expected = Some(b"long".as_slice());
+        assert_eq!(archive.read_file(&target).as_deref(), expected);
+        assert_eq!(archive.read_file(&link).as_deref(), expected);
+        assert_eq!(archive.read_file("symlink").as_deref(), expected);
+        assert_eq!(archive.read_file("hardlink"), None);
+    }
+
     /// Test that the entry's contents match the expected content.
     fn assert_entry_content(entry: &ArchiveEntry, filename: &str, size: usize) {
         assert_eq!(entry.filename().as_str(), Ok(filename));
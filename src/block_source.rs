@@ -0,0 +1,255 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`BlockSource`], which abstracts over the memory that holds the
+//! blocks of an archive. This enables parsing archives that are not stored in
+//! one contiguous slice, such as a DMA ring buffer.
+
+use crate::{PosixHeader, BLOCKSIZE};
+use core::fmt::{Debug, Formatter};
+use core::ops::Deref;
+
+/// A block of an archive that is provided by a [`BlockSource`]. It either
+/// borrows the underlying memory or is a copy of it, if the block isn't
+/// contiguous in memory.
+#[derive(Copy, Clone, PartialEq, Eq)]
+// Boxing the copy would require an allocator.
+#[allow(clippy::large_enum_variant)]
+pub enum Block<'a> {
+    /// The block is contiguous in the memory of the source.
+    Borrowed(&'a [u8; BLOCKSIZE]),
+    /// The block was copied, e.g., because it wraps around the end of a
+    /// [`RingBuffer`].
+    Copied([u8; BLOCKSIZE]),
+}
+
+impl Block<'_> {
+    /// Interprets the block as [`PosixHeader`].
+    #[must_use]
+    pub fn as_header(&self) -> &PosixHeader {
+        // SAFETY: The header is exactly one block long and has an alignment
+        // of 1, as it is packed.
+        unsafe { &*self.as_ptr().cast::<PosixHeader>() }
+    }
+}
+
+impl Deref for Block<'_> {
+    type Target = [u8; BLOCKSIZE];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(block) => block,
+            Self::Copied(block) => block,
+        }
+    }
+}
+
+impl Debug for Block<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let kind = match self {
+            Self::Borrowed(_) => "Borrowed",
+            Self::Copied(_) => "Copied",
+        };
+        f.debug_tuple(kind).field(&"<bytes>").finish()
+    }
+}
+
+/// Source of the blocks of a Tar archive.
+pub trait BlockSource {
+    /// Number of complete blocks in the source.
+    fn block_count(&self) -> usize;
+
+    /// Returns the block at the given index or `None`, if the index is out of
+    /// bounds.
+    fn block(&self, index: usize) -> Option<Block<'_>>;
+
+    /// Iterates over the headers of the archive. See [`SourceHeaderIterator`].
+    fn headers(&self) -> SourceHeaderIterator<'_, Self> {
+        SourceHeaderIterator {
+            source: self,
+            next_hdr_block_index: 0,
+        }
+    }
+}
+
+impl BlockSource for [u8] {
+    /// Trailing bytes that don't form a complete block are ignored.
+    fn block_count(&self) -> usize {
+        self.len() / BLOCKSIZE
+    }
+
+    fn block(&self, index: usize) -> Option<Block<'_>> {
+        let begin = index.checked_mul(BLOCKSIZE)?;
+        let block = self.get(begin..begin.checked_add(BLOCKSIZE)?)?;
+        Some(Block::Borrowed(block.try_into().unwrap()))
+    }
+}
+
+/// Reference implementation of a [`BlockSource`] over a circular buffer.
+///
+/// The archive starts at the `head` offset of the buffer and wraps around at
+/// its end. Blocks that are contiguous in the buffer are borrowed. Blocks
+/// that straddle the end of the buffer are copied into a [`Block::Copied`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RingBuffer<'a> {
+    buffer: &'a [u8],
+    head: usize,
+    len: usize,
+}
+
+impl<'a> RingBuffer<'a> {
+    /// Creates a new ring buffer source with `len` bytes of data starting at
+    /// the `head` offset of the buffer.
+    ///
+    /// Returns `None`, if the head is out of bounds or if the buffer can't
+    /// hold `len` bytes.
+    #[must_use]
+    pub const fn new(buffer: &'a [u8], head: usize, len: usize) -> Option<Self> {
+        if head >= buffer.len() || len > buffer.len() {
+            return None;
+        }
+        Some(Self { buffer, head, len })
+    }
+}
+
+impl BlockSource for RingBuffer<'_> {
+    fn block_count(&self) -> usize {
+        self.len / BLOCKSIZE
+    }
+
+    fn block(&self, index: usize) -> Option<Block<'_>> {
+        if index >= self.block_count() {
+            return None;
+        }
+        let begin = (self.head + index * BLOCKSIZE) % self.buffer.len();
+        if let Some(block) = self.buffer.get(begin..begin + BLOCKSIZE) {
+            return Some(Block::Borrowed(block.try_into().unwrap()));
+        }
+
+        // The block wraps around the end of the buffer.
+        let mut block = [0; BLOCKSIZE];
+        let (first, second) = block.split_at_mut(self.buffer.len() - begin);
+        first.copy_from_slice(&self.buffer[begin..]);
+        second.copy_from_slice(&self.buffer[..second.len()]);
+        Some(Block::Copied(block))
+    }
+}
+
+/// Iterates over the headers of a [`BlockSource`]. It skips the payload of
+/// each entry and stops at the terminating zero blocks.
+///
+/// This is the counterpart of [`crate::ArchiveHeaderIterator`]. The payload
+/// can be read with [`BlockSource::block`], as it starts at the block after
+/// the header.
+#[derive(Debug)]
+pub struct SourceHeaderIterator<'a, S: BlockSource + ?Sized> {
+    source: &'a S,
+    next_hdr_block_index: usize,
+}
+
+impl<'a, S: BlockSource + ?Sized> Iterator for SourceHeaderIterator<'a, S> {
+    type Item = (usize, Block<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block_index = self.next_hdr_block_index;
+        let block = self.source.block(block_index)?;
+        let hdr = block.as_header();
+        if hdr.is_zero_block() {
+            return None;
+        }
+
+        let payload_block_count = if hdr.typeflag.has_payload() {
+            hdr.payload_block_count()
+                .inspect_err(|e| {
+                    log_header!(
+                        error,
+                        block_index,
+                        hdr,
+                        "Unparsable size ({e:?}) in header {hdr:#?}"
+                    );
+                })
+                .ok()?
+        } else {
+            0
+        };
+        self.next_hdr_block_index = block_index
+            .saturating_add(1)
+            .saturating_add(payload_block_count);
+        Some((block_index, block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArchiveHeaderIterator;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_slice_source() {
+        let archive = include_bytes!("../tests/gnu_tar_default.tar");
+        let expected = ArchiveHeaderIterator::new(archive)
+            .map(|(index, hdr)| (index, *hdr))
+            .collect::<Vec<_>>();
+        let headers = archive[..]
+            .headers()
+            .map(|(index, block)| (index, *block.as_header()))
+            .collect::<Vec<_>>();
+        assert_eq!(headers, expected);
+        assert_eq!(archive[..].block(archive.len() / BLOCKSIZE), None);
+    }
+
+    #[test]
+    fn test_ring_buffer_source() {
+        let archive = include_bytes!("../tests/gnu_tar_default.tar");
+        let expected = ArchiveHeaderIterator::new(archive)
+            .map(|(index, hdr)| (index, *hdr))
+            .collect::<Vec<_>>();
+
+        // Rotate the archive, so that the fourth block (the second header)
+        // straddles the end of the buffer.
+        let head = archive.len() - 3 * BLOCKSIZE - 100;
+        let mut buffer = std::vec![0; archive.len()];
+        buffer[head..].copy_from_slice(&archive[..archive.len() - head]);
+        buffer[..head].copy_from_slice(&archive[archive.len() - head..]);
+
+        let ring = RingBuffer::new(&buffer, head, archive.len()).unwrap();
+        assert_eq!(ring.block_count(), archive.len() / BLOCKSIZE);
+        let headers = ring.headers().collect::<Vec<_>>();
+        assert!(matches!(headers[0].1, Block::Borrowed(_)));
+        assert!(matches!(headers[1].1, Block::Copied(_)));
+        assert!(matches!(headers[2].1, Block::Borrowed(_)));
+        let headers = headers
+            .iter()
+            .map(|(index, block)| (*index, *block.as_header()))
+            .collect::<Vec<_>>();
+        assert_eq!(headers, expected);
+
+        // data of the last file
+        let (index, _) = ring.headers().last().unwrap();
+        assert!(ring.block(index + 1).unwrap().starts_with(b"Hello World\n"));
+
+        assert_eq!(RingBuffer::new(&buffer, buffer.len(), 0), None);
+        assert_eq!(RingBuffer::new(&buffer, 0, buffer.len() + 1), None);
+    }
+}
@@ -30,9 +30,10 @@ SOFTWARE.
 
 #![allow(non_upper_case_globals)]
 
-use crate::{TarFormatDecimal, TarFormatOctal, TarFormatString, BLOCKSIZE, NAME_LEN, PREFIX_LEN};
+use crate::{TarFormatOctal, TarFormatString, BLOCKSIZE, NAME_LEN, PREFIX_LEN};
 use core::fmt::{Debug, Display, Formatter};
 use core::num::ParseIntError;
+use core::str::Utf8Error;
 
 /// Errors that may happen when parsing the [`ModeFlags`].
 #[derive(Debug)]
@@ -54,12 +55,46 @@ impl Mode {
     }
 }
 
+impl From<ModeFlags> for Mode {
+    fn from(flags: ModeFlags) -> Self {
+        // All valid mode bits fit into the field.
+        Self(TarFormatOctal::from_number(flags.bits()).unwrap())
+    }
+}
+
 impl Debug for Mode {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Debug::fmt(&self.to_flags(), f)
     }
 }
 
+/// Serializes the permission bits as number or `None`, if they can't be
+/// parsed.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Mode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.as_number::<u64>().ok().serialize(serializer)
+    }
+}
+
+/// Point in time in seconds since the Unix epoch (1970-01-01 00:00:00 UTC),
+/// as used by the `mtime` field of the header. PAX extended headers can also
+/// describe points in time before 1970.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnixTimestamp(pub i64);
+
+impl From<UnixTimestamp> for i64 {
+    fn from(timestamp: UnixTimestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl Debug for UnixTimestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "UnixTimestamp({} seconds since 1970)", self.0)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq)]
 pub struct InvalidTypeFlagError(u8);
 
@@ -72,6 +107,32 @@ impl Display for InvalidTypeFlagError {
 #[cfg(feature = "unstable")]
 impl core::error::Error for InvalidTypeFlagError {}
 
+/// The `cksum` field of a header doesn't match the checksum of the header.
+/// See [`PosixHeader::verify_checksum`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChecksumError {
+    /// The value of the `cksum` field, or `None`, if it can't be parsed.
+    pub stored: Option<u64>,
+    /// The checksum computed from the bytes of the header.
+    pub computed: u64,
+}
+
+impl Display for ChecksumError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.stored {
+            Some(stored) => write!(
+                f,
+                "header checksum mismatch: stored {stored}, computed {}",
+                self.computed
+            ),
+            None => write!(f, "unparsable header checksum, computed {}", self.computed),
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for ChecksumError {}
+
 #[derive(Copy, Clone, PartialOrd, PartialEq, Eq)]
 pub struct TypeFlagRaw(u8);
 
@@ -83,18 +144,112 @@ impl TypeFlagRaw {
     }
 }
 
+impl TypeFlagRaw {
+    /// Creates a typeflag from a raw byte, e.g., for GNU extensions that
+    /// have no [`TypeFlag`].
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    pub(crate) const fn from_byte(byte: u8) -> Self {
+        Self(byte)
+    }
+
+    /// Returns the raw byte of the typeflag field.
+    pub(crate) const fn as_byte(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<TypeFlag> for TypeFlagRaw {
+    fn from(typeflag: TypeFlag) -> Self {
+        Self(typeflag as u8)
+    }
+}
+
+impl TypeFlagRaw {
+    /// Whether the header is followed by payload blocks. Unknown types are
+    /// handled like regular files, as the spec demands.
+    pub(crate) fn has_payload(self) -> bool {
+        self.try_to_type_flag().map_or(true, |typeflag| {
+            !matches!(
+                typeflag,
+                TypeFlag::LINK
+                    | TypeFlag::SYMTYPE
+                    | TypeFlag::CHRTYPE
+                    | TypeFlag::BLKTYPE
+                    | TypeFlag::DIRTYPE
+                    | TypeFlag::FIFOTYPE
+            )
+        })
+    }
+}
+
 impl Debug for TypeFlagRaw {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Debug::fmt(&self.try_to_type_flag(), f)
     }
 }
 
+/// Serializes the raw typeflag as character, such as `'5'` for directories.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TypeFlagRaw {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(char::from(self.as_byte()))
+    }
+}
+
+/// Set of raw typeflag bytes, such as `b'5'` for directories. This also
+/// covers typeflags that [`TypeFlag`] doesn't know, such as the GNU
+/// extensions. See [`crate::ArchiveEntryIterator::skipped_types`].
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct TypeFlagSet([u64; 4]);
+
+impl TypeFlagSet {
+    /// Creates an empty set.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self([0; 4])
+    }
+
+    /// Adds the typeflag to the set.
+    pub fn insert(&mut self, typeflag: u8) {
+        self.0[usize::from(typeflag / 64)] |= 1 << (typeflag % 64);
+    }
+
+    /// Whether the set contains the typeflag.
+    #[must_use]
+    pub const fn contains(&self, typeflag: u8) -> bool {
+        self.0[(typeflag / 64) as usize] & (1 << (typeflag % 64)) != 0
+    }
+
+    /// Whether the set is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|bits| *bits == 0)
+    }
+
+    /// Number of typeflags in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+
+    /// Iterates over the typeflags in the set in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|typeflag| self.contains(*typeflag))
+    }
+}
+
+impl Debug for TypeFlagSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_set().entries(self.iter().map(char::from)).finish()
+    }
+}
+
 /// Describes the kind of payload, that follows after a
 /// [`PosixHeader`]. The properties of this payload are
 /// described inside the header.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
-#[allow(unused)]
+#[allow(unused, non_camel_case_types)]
 pub enum TypeFlag {
     /// Represents a regular file. In order to be compatible with older versions of tar, a typeflag
     /// value of AREGTYPE should be silently recognized as a regular file. New archives should be
@@ -142,15 +297,99 @@ pub enum TypeFlag {
     XHDTYPE = b'x',
     /// Global extended header
     XGLTYPE = b'g',
+    /// GNU long name: the payload is the name of the next entry, if it
+    /// doesn't fit into the header (`././@LongLink`).
+    GNU_LONGNAME = b'L',
+    /// GNU long link: the payload is the link target of the next entry, if
+    /// it doesn't fit into the header.
+    GNU_LONGLINK = b'K',
 }
 
 impl TypeFlag {
+    /// All types in the order of their declaration.
+    pub const ALL: [Self; 13] = [
+        Self::REGTYPE,
+        Self::AREGTYPE,
+        Self::LINK,
+        Self::SYMTYPE,
+        Self::CHRTYPE,
+        Self::BLKTYPE,
+        Self::DIRTYPE,
+        Self::FIFOTYPE,
+        Self::CONTTYPE,
+        Self::XHDTYPE,
+        Self::XGLTYPE,
+        Self::GNU_LONGNAME,
+        Self::GNU_LONGLINK,
+    ];
+
+    /// Position of the type in [`Self::ALL`].
+    pub(crate) const fn index(self) -> usize {
+        match self {
+            Self::REGTYPE => 0,
+            Self::AREGTYPE => 1,
+            Self::LINK => 2,
+            Self::SYMTYPE => 3,
+            Self::CHRTYPE => 4,
+            Self::BLKTYPE => 5,
+            Self::DIRTYPE => 6,
+            Self::FIFOTYPE => 7,
+            Self::CONTTYPE => 8,
+            Self::XHDTYPE => 9,
+            Self::XGLTYPE => 10,
+            Self::GNU_LONGNAME => 11,
+            Self::GNU_LONGLINK => 12,
+        }
+    }
+
     /// Whether we have a regular file.
     #[must_use]
     pub fn is_regular_file(self) -> bool {
         // Equivalent. See spec.
         self == Self::AREGTYPE || self == Self::REGTYPE
     }
+
+    /// Whether the type holds the data of a file, i.e., a regular file or a
+    /// contiguous file. The spec demands that contiguous files are handled
+    /// like regular files on systems without contiguous allocation.
+    #[must_use]
+    pub const fn is_file_like(self) -> bool {
+        matches!(self.category(), EntryCategory::File)
+    }
+
+    /// The coarse category of the type, for code that handles several types
+    /// the same way.
+    #[must_use]
+    pub const fn category(self) -> EntryCategory {
+        match self {
+            Self::REGTYPE | Self::AREGTYPE | Self::CONTTYPE => EntryCategory::File,
+            Self::DIRTYPE => EntryCategory::Dir,
+            Self::LINK | Self::SYMTYPE => EntryCategory::Link,
+            Self::CHRTYPE | Self::BLKTYPE => EntryCategory::Device,
+            Self::FIFOTYPE => EntryCategory::Fifo,
+            Self::XHDTYPE | Self::XGLTYPE | Self::GNU_LONGNAME | Self::GNU_LONGLINK => {
+                EntryCategory::Extension
+            }
+        }
+    }
+}
+
+/// Coarse category of a [`TypeFlag`]. See [`TypeFlag::category`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryCategory {
+    /// Regular and contiguous files.
+    File,
+    /// Directories.
+    Dir,
+    /// Hardlinks and symlinks.
+    Link,
+    /// Character and block devices.
+    Device,
+    /// FIFOs (named pipes).
+    Fifo,
+    /// Headers that extend the following entries, such as PAX extended
+    /// headers and GNU long names.
+    Extension,
 }
 
 impl TryFrom<TypeFlagRaw> for TypeFlag {
@@ -169,6 +408,8 @@ impl TryFrom<TypeFlagRaw> for TypeFlag {
             b'7' => Ok(Self::CONTTYPE),
             b'x' => Ok(Self::XHDTYPE),
             b'g' => Ok(Self::XGLTYPE),
+            b'L' => Ok(Self::GNU_LONGNAME),
+            b'K' => Ok(Self::GNU_LONGLINK),
             e => Err(InvalidTypeFlagError(e)),
         }
     }
@@ -206,6 +447,87 @@ bitflags::bitflags! {
     }
 }
 
+impl ModeFlags {
+    /// Returns the permissions in the symbolic notation of `ls -l`, such as
+    /// `rwxr-sr-t`.
+    ///
+    /// The setuid, setgid, and sticky bits replace the corresponding execute
+    /// bit with `s` (or `t`), or with `S` (or `T`), if the execute bit isn't
+    /// set. The returned bytes are always ASCII.
+    #[must_use]
+    pub const fn to_symbolic(self) -> [u8; 9] {
+        const fn bit(flags: ModeFlags, flag: ModeFlags, symbol: u8) -> u8 {
+            if flags.contains(flag) {
+                symbol
+            } else {
+                b'-'
+            }
+        }
+        const fn exec(flags: ModeFlags, exec: ModeFlags, special: ModeFlags, symbol: u8) -> u8 {
+            match (flags.contains(exec), flags.contains(special)) {
+                (true, true) => symbol,
+                (false, true) => symbol.to_ascii_uppercase(),
+                (true, false) => b'x',
+                (false, false) => b'-',
+            }
+        }
+        [
+            bit(self, Self::OwnerRead, b'r'),
+            bit(self, Self::OwnerWrite, b'w'),
+            exec(self, Self::OwnerExec, Self::SetUID, b's'),
+            bit(self, Self::GroupRead, b'r'),
+            bit(self, Self::GroupWrite, b'w'),
+            exec(self, Self::GroupExec, Self::SetGID, b's'),
+            bit(self, Self::OthersRead, b'r'),
+            bit(self, Self::OthersWrite, b'w'),
+            exec(self, Self::OthersExec, Self::TSVTX, b't'),
+        ]
+    }
+}
+
+/// Prints the permissions in octal with a leading zero, such as `0644` or
+/// `04755`. See [`ModeFlags::to_symbolic`] for the notation of `ls -l`.
+impl Display for ModeFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "0{:03o}", self.bits())
+    }
+}
+
+/// Format of a header, as given by its `magic` and `version` fields. See
+/// [`PosixHeader::version_kind`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VersionKind {
+    /// POSIX ustar format (`"ustar\0"` and `"00"`), which is also used by PAX
+    /// archives. Long filenames are split into `prefix` and `name`.
+    Ustar,
+    /// GNU and old GNU format (`"ustar "` and `" \0"` or `"\0 "`). The
+    /// `prefix` field holds other data, such as the access time.
+    Gnu,
+    /// Both fields are empty, as in the historic V7 format.
+    V7,
+    /// Any other combination.
+    Unknown,
+}
+
+/// Flavor of a Tar archive. See [`PosixHeader::detect_format`].
+///
+/// GNU Tar writes the same `magic` and `version` in its `gnu` and `oldgnu`
+/// formats, so they can't be told apart by the header and both are reported
+/// as [`ArchiveFormat::Gnu`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// The historic V7 format without `magic` and `version`.
+    V7,
+    /// POSIX ustar format.
+    Ustar,
+    /// GNU format.
+    Gnu,
+    /// POSIX PAX format, i.e., ustar with PAX extended headers.
+    Pax,
+    /// Any other combination of `magic` and `version`.
+    Unknown,
+}
+
 /// Header of the TAR format as specified by POSIX (POSIX 1003.1-1990.
 ///
 /// "New" (version?) GNU Tar versions use this archive format by default.
@@ -227,7 +549,8 @@ pub struct PosixHeader {
     pub gid: TarFormatOctal<8>,
     // confusing; size is stored as ASCII string
     pub size: TarFormatOctal<12>,
-    pub mtime: TarFormatDecimal<12>,
+    /// Modification time in seconds since the Unix epoch.
+    pub mtime: TarFormatOctal<12>,
     pub cksum: TarFormatOctal<8>,
     pub typeflag: TypeFlagRaw,
     /// Name. There is always a null byte, therefore
@@ -244,11 +567,48 @@ pub struct PosixHeader {
     pub dev_major: TarFormatOctal<8>,
     pub dev_minor: TarFormatOctal<8>,
     pub prefix: TarFormatString<PREFIX_LEN>,
-    // padding => to BLOCKSIZE bytes
+    /// Padding to 512 bytes. Use [`Self::trailing_bytes`] to read it.
     pub _pad: [u8; 12],
 }
 
+/// Serializes the parsed values of the fields. The padding is omitted.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PosixHeader {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut hdr = serializer.serialize_struct("PosixHeader", 16)?;
+        hdr.serialize_field("name", &self.name)?;
+        hdr.serialize_field("mode", &self.mode)?;
+        hdr.serialize_field("uid", &self.uid)?;
+        hdr.serialize_field("gid", &self.gid)?;
+        hdr.serialize_field("size", &self.size)?;
+        hdr.serialize_field("mtime", &self.mtime)?;
+        hdr.serialize_field("cksum", &self.cksum)?;
+        hdr.serialize_field("typeflag", &self.typeflag)?;
+        hdr.serialize_field("linkname", &self.linkname)?;
+        hdr.serialize_field("magic", &self.magic)?;
+        hdr.serialize_field("version", &self.version)?;
+        hdr.serialize_field("uname", &self.uname)?;
+        hdr.serialize_field("gname", &self.gname)?;
+        hdr.serialize_field("dev_major", &self.dev_major)?;
+        hdr.serialize_field("dev_minor", &self.dev_minor)?;
+        hdr.serialize_field("prefix", &self.prefix)?;
+        hdr.end()
+    }
+}
+
 impl PosixHeader {
+    /// Reads a header from a block of exactly 512 bytes. The slice
+    /// doesn't need to be aligned. Returns `None` for slices of a different
+    /// length.
+    #[must_use]
+    pub fn from_block(block: &[u8]) -> Option<Self> {
+        let block: &[u8; BLOCKSIZE] = block.try_into().ok()?;
+        // SAFETY: The header consists only of byte arrays, so every bit
+        // pattern of the right length is valid.
+        Some(unsafe { block.as_ptr().cast::<Self>().read_unaligned() })
+    }
+
     /// Returns the number of blocks that are required to read the whole file
     /// content. Returns an error, if the file size can't be parsed from the
     /// header.
@@ -257,25 +617,298 @@ impl PosixHeader {
         Ok(parsed_size.div_ceil(BLOCKSIZE))
     }
 
+    /// The format of the header according to its `magic` and `version`
+    /// fields.
+    #[must_use]
+    pub const fn version_kind(&self) -> VersionKind {
+        match (self.magic.as_bytes(), self.version.as_bytes()) {
+            (b"ustar\0", b"00") => VersionKind::Ustar,
+            (b"ustar ", b" \0" | b"\0 ") => VersionKind::Gnu,
+            (b"\0\0\0\0\0\0", b"\0\0") => VersionKind::V7,
+            _ => VersionKind::Unknown,
+        }
+    }
+
+    /// Whether the `prefix` field holds the leading part of the name. This is
+    /// only the case for ustar (and thus PAX) headers with a non-empty
+    /// prefix.
+    ///
+    /// Headers with the old GNU magic (`"ustar "` with version `" \0"`) are
+    /// deliberately excluded: GNU Tar stores the access time, the change time,
+    /// and the offset of multi-volume archives in these bytes (see
+    /// [`Self::gnu_atime`]), which would otherwise be prepended to the name.
+    /// Old GNU archives store long names in GNU long name records instead.
+    #[must_use]
+    pub const fn uses_prefix(&self) -> bool {
+        matches!(self.version_kind(), VersionKind::Ustar) && !self.prefix.is_empty()
+    }
+
+    /// The access time of an old GNU header in seconds since the Unix epoch.
+    /// GNU Tar stores it in the first 12 bytes of the bytes that ustar uses
+    /// for the `prefix`, e.g., for incremental backups.
+    ///
+    /// Returns `None` for headers of other formats, as their `prefix` holds
+    /// a name, and if the field is empty.
+    #[must_use]
+    pub fn gnu_atime(&self) -> Option<TarFormatOctal<12>> {
+        self.gnu_time_at(0)
+    }
+
+    /// The status change time of an old GNU header in seconds since the Unix
+    /// epoch. It directly follows the access time. See [`Self::gnu_atime`].
+    #[must_use]
+    pub fn gnu_ctime(&self) -> Option<TarFormatOctal<12>> {
+        self.gnu_time_at(12)
+    }
+
+    /// Reads the 12 bytes at the given offset of the `prefix` of an old GNU
+    /// header as timestamp.
+    fn gnu_time_at(&self, offset: usize) -> Option<TarFormatOctal<12>> {
+        if !matches!(self.version_kind(), VersionKind::Gnu) {
+            return None;
+        }
+        let bytes = self.prefix.as_bytes()[offset..offset + 12]
+            .try_into()
+            .unwrap();
+        let time = TarFormatOctal::new(bytes);
+        (!time.as_inner().is_empty()).then_some(time)
+    }
+
+    /// The flavor of the archive according to the `magic` and `version`
+    /// fields. PAX extended headers (typeflags `x` and `g`) are reported as
+    /// [`ArchiveFormat::Pax`].
+    #[must_use]
+    pub const fn detect_format(&self) -> ArchiveFormat {
+        if matches!(self.typeflag.as_byte(), b'x' | b'g') {
+            return ArchiveFormat::Pax;
+        }
+        match self.version_kind() {
+            VersionKind::Ustar => ArchiveFormat::Ustar,
+            VersionKind::Gnu => ArchiveFormat::Gnu,
+            VersionKind::V7 => ArchiveFormat::V7,
+            VersionKind::Unknown => ArchiveFormat::Unknown,
+        }
+    }
+
+    /// A 64-bit FNV-1a hash over the raw bytes of the `name`, `prefix`,
+    /// `size`, `mtime`, and `typeflag` fields. It is stable across platforms
+    /// and versions of this crate, so it can be used as key for caches of
+    /// parse results, e.g., to detect whether an entry changed when an
+    /// archive is read again.
+    ///
+    /// The `prefix` is only included, if it is part of the name, i.e., if
+    /// [`Self::uses_prefix`]. Other formats store unrelated data there, such
+    /// as the access time.
+    ///
+    /// This only covers metadata, not the payload. It is not cryptographic
+    /// and must not be used to detect tampering.
+    #[must_use]
+    pub fn metadata_digest(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let prefix: &[u8] = if self.uses_prefix() {
+            self.prefix.as_bytes()
+        } else {
+            &[]
+        };
+        [
+            &self.name.as_bytes()[..],
+            prefix,
+            self.size.as_inner().as_bytes(),
+            self.mtime.as_inner().as_bytes(),
+            &[self.typeflag.as_byte()],
+        ]
+        .iter()
+        .flat_map(|field| field.iter())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        })
+    }
+
     /// A Tar archive is terminated, if an end-of-archive entry, which consists
     /// of two 512 blocks of zero bytes, is found.
     #[must_use]
     pub fn is_zero_block(&self) -> bool {
-        let ptr = self as *const Self as *const u8;
-        let self_bytes = unsafe { core::slice::from_raw_parts(ptr, BLOCKSIZE) };
-        self_bytes.iter().filter(|x| **x == 0).count() == BLOCKSIZE
+        // Compare word-wise and stop at the first non-zero word, as this runs
+        // for every header. The bytes are read through a byte slice, so the
+        // packed layout needs no alignment.
+        self.as_bytes()
+            .chunks_exact(core::mem::size_of::<u64>())
+            .all(|word| u64::from_ne_bytes(word.try_into().unwrap()) == 0)
+    }
+
+    /// Returns the raw bytes of the header, i.e., the block as it is stored
+    /// in the archive. This helps to hash or to copy a header verbatim.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; BLOCKSIZE] {
+        let ptr = self as *const Self as *const [u8; BLOCKSIZE];
+        unsafe { &*ptr }
+    }
+
+    /// Computes the checksum of the header, which is the sum of all header
+    /// bytes where the bytes of the `cksum` field are treated as ASCII spaces.
+    pub(crate) fn compute_checksum(&self) -> u64 {
+        let cksum_bytes = self.cksum.as_inner().as_bytes();
+        let sum = |bytes: &[u8]| bytes.iter().map(|x| u64::from(*x)).sum::<u64>();
+        sum(self.as_bytes()) - sum(cksum_bytes) + sum(&[b' '; 8])
+    }
+
+    /// Verifies the `cksum` field. The checksum is the sum of all bytes of
+    /// the header, where the bytes of the `cksum` field itself are treated
+    /// as ASCII spaces.
+    pub fn verify_checksum(&self) -> Result<(), ChecksumError> {
+        let stored = self.cksum.as_number::<u64>().ok();
+        let computed = self.compute_checksum();
+        if stored == Some(computed) {
+            Ok(())
+        } else {
+            Err(ChecksumError { stored, computed })
+        }
+    }
+
+    /// Whether the `cksum` field matches the checksum of the header. See
+    /// [`Self::verify_checksum`] for the details of a mismatch.
+    #[must_use]
+    pub fn checksum_is_valid(&self) -> bool {
+        self.verify_checksum().is_ok()
+    }
+
+    /// Updates the `cksum` field with the checksum of the header, see
+    /// [`Self::verify_checksum`]. Like GNU Tar, this uses six octal digits
+    /// followed by a NULL byte and a space.
+    ///
+    /// This must be called after modifying a header, e.g., when building
+    /// headers by hand.
+    pub fn recompute_checksum(&mut self) {
+        let mut checksum = self.compute_checksum();
+        let mut bytes = [0; 8];
+        bytes[7] = b' ';
+        for byte in bytes[..6].iter_mut().rev() {
+            *byte = b'0' + (checksum % 8) as u8;
+            checksum /= 8;
+        }
+        self.cksum = TarFormatOctal::new(bytes);
+    }
+
+    /// Returns a copy of the last 12 bytes of the header (the `_pad` field).
+    ///
+    /// The content depends on the Tar flavor:
+    /// - ustar (POSIX) and GNU: unused, usually all zero
+    /// - star/xstar (by Jörg Schilling): 8 filler bytes followed by the
+    ///   `"tar\0"` magic
+    ///
+    /// Other producers might store arbitrary data in this region.
+    #[must_use]
+    pub const fn trailing_bytes(&self) -> [u8; 12] {
+        unsafe { core::ptr::addr_of!(self._pad).read_unaligned() }
+    }
+}
+
+/// The commonly used fields of a [`PosixHeader`], parsed at once. This is
+/// faster than the accessors, which parse the fields on every call, when
+/// listing many entries. The strings borrow from the header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HeaderMetadata<'a> {
+    /// The `name` field, without the `prefix`.
+    pub name: &'a str,
+    /// The `prefix` field, which holds the leading part of the name in ustar
+    /// headers. See [`PosixHeader::uses_prefix`].
+    pub prefix: &'a str,
+    /// UNIX file permissions.
+    pub mode: ModeFlags,
+    /// Numeric user ID of the owner.
+    pub uid: u64,
+    /// Numeric group ID of the owner.
+    pub gid: u64,
+    /// Size of the payload in bytes.
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch.
+    pub mtime: u64,
+    /// Type of the entry.
+    pub typeflag: TypeFlag,
+    /// Target of a hardlink or symlink.
+    pub linkname: &'a str,
+}
+
+/// The first field that can't be parsed when creating a [`HeaderMetadata`].
+#[derive(Debug)]
+pub enum HeaderMetadataError {
+    /// The `name` field isn't valid UTF-8.
+    Name(Utf8Error),
+    /// The `prefix` field isn't valid UTF-8.
+    Prefix(Utf8Error),
+    /// The `mode` field can't be parsed.
+    Mode(ModeError),
+    /// The `uid` field can't be parsed.
+    Uid(ParseIntError),
+    /// The `gid` field can't be parsed.
+    Gid(ParseIntError),
+    /// The `size` field can't be parsed.
+    Size(ParseIntError),
+    /// The `mtime` field can't be parsed.
+    Mtime(ParseIntError),
+    /// The `typeflag` field holds an unknown type.
+    TypeFlag(InvalidTypeFlagError),
+    /// The `linkname` field isn't valid UTF-8.
+    Linkname(Utf8Error),
+}
+
+impl Display for HeaderMetadataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Name(e) => write!(f, "invalid name: {e}"),
+            Self::Prefix(e) => write!(f, "invalid prefix: {e}"),
+            Self::Mode(e) => write!(f, "invalid mode: {e:?}"),
+            Self::Uid(e) => write!(f, "invalid uid: {e}"),
+            Self::Gid(e) => write!(f, "invalid gid: {e}"),
+            Self::Size(e) => write!(f, "invalid size: {e}"),
+            Self::Mtime(e) => write!(f, "invalid mtime: {e}"),
+            Self::TypeFlag(e) => Display::fmt(e, f),
+            Self::Linkname(e) => write!(f, "invalid linkname: {e}"),
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for HeaderMetadataError {}
+
+impl<'a> TryFrom<&'a PosixHeader> for HeaderMetadata<'a> {
+    type Error = HeaderMetadataError;
+
+    fn try_from(hdr: &'a PosixHeader) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: hdr.name.as_str().map_err(HeaderMetadataError::Name)?,
+            prefix: hdr.prefix.as_str().map_err(HeaderMetadataError::Prefix)?,
+            mode: hdr.mode.to_flags().map_err(HeaderMetadataError::Mode)?,
+            uid: hdr.uid.as_number().map_err(HeaderMetadataError::Uid)?,
+            gid: hdr.gid.as_number().map_err(HeaderMetadataError::Gid)?,
+            size: hdr.size.as_number().map_err(HeaderMetadataError::Size)?,
+            mtime: hdr.mtime.as_number().map_err(HeaderMetadataError::Mtime)?,
+            typeflag: hdr
+                .typeflag
+                .try_to_type_flag()
+                .map_err(HeaderMetadataError::TypeFlag)?,
+            linkname: hdr
+                .linkname
+                .as_str()
+                .map_err(HeaderMetadataError::Linkname)?,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::header::{PosixHeader, TypeFlag};
-    use crate::BLOCKSIZE;
+    use crate::header::{
+        HeaderMetadata, HeaderMetadataError, ModeFlags, PosixHeader, TypeFlag, TypeFlagSet,
+        VersionKind,
+    };
+    use crate::{TarFormatOctal, TarFormatString};
+    use crate::{BLOCKSIZE, PREFIX_LEN};
     use std::mem::size_of;
 
     /// Returns the PosixHeader at the beginning of the Tar archive.
-    fn bytes_to_archive(tar_archive_data: &[u8]) -> &PosixHeader {
-        unsafe { (tar_archive_data.as_ptr() as *const PosixHeader).as_ref() }.unwrap()
+    fn bytes_to_archive(tar_archive_data: &[u8]) -> PosixHeader {
+        PosixHeader::from_block(&tar_archive_data[..BLOCKSIZE]).unwrap()
     }
 
     #[test]
@@ -331,6 +964,151 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_header_metadata() {
+        let hdr = bytes_to_archive(include_bytes!("../tests/gnu_tar_default.tar"));
+        let metadata = HeaderMetadata::try_from(&hdr).unwrap();
+        assert_eq!(metadata.name, "bye_world_513b.txt");
+        assert_eq!(metadata.prefix, "");
+        assert_eq!(metadata.mode.bits(), 0o664);
+        assert_eq!(metadata.uid, 1001);
+        assert_eq!(metadata.gid, 1001);
+        assert_eq!(metadata.size, 513);
+        assert_eq!(metadata.mtime, 1_633_330_466);
+        assert_eq!(metadata.typeflag, TypeFlag::REGTYPE);
+        assert_eq!(metadata.linkname, "");
+
+        let mut hdr = hdr;
+        hdr.gid = TarFormatOctal::new(*b"12x45\0\0\0");
+        assert!(matches!(
+            HeaderMetadata::try_from(&hdr),
+            Err(HeaderMetadataError::Gid(_))
+        ));
+    }
+
+    #[test]
+    fn test_is_zero_block() {
+        let reference = |hdr: &PosixHeader| hdr.as_bytes().iter().all(|&b| b == 0);
+
+        let fixtures: [&[u8]; 4] = [
+            include_bytes!("../tests/gnu_tar_default.tar"),
+            include_bytes!("../tests/gnu_tar_pax.tar"),
+            include_bytes!("../tests/gnu_tar_ustar_long.tar"),
+            include_bytes!("../tests/weird_fuzzing_tarballs.tar"),
+        ];
+        let mut zero_blocks = 0;
+        for data in fixtures {
+            for block in data.chunks_exact(BLOCKSIZE) {
+                let hdr = bytes_to_archive(block);
+                assert_eq!(hdr.is_zero_block(), reference(&hdr));
+                zero_blocks += usize::from(hdr.is_zero_block());
+            }
+        }
+        assert!(zero_blocks > 0);
+
+        // a single non-zero byte at any position
+        for index in 0..BLOCKSIZE {
+            let mut block = [0; BLOCKSIZE];
+            assert!(bytes_to_archive(&block).is_zero_block());
+            block[index] = 1;
+            assert!(!bytes_to_archive(&block).is_zero_block());
+        }
+    }
+
+    #[test]
+    fn test_uses_prefix() {
+        let mut hdr = bytes_to_archive(include_bytes!("../tests/gnu_tar_oldgnu.tar"));
+        assert!(!hdr.uses_prefix());
+        // Old GNU headers store the access time where ustar has the prefix.
+        hdr.prefix = TarFormatString::try_from_str("14124356127").unwrap();
+        assert!(!hdr.uses_prefix());
+
+        hdr.magic = TarFormatString::new(*b"ustar\0");
+        hdr.version = TarFormatString::new(*b"00");
+        assert!(hdr.uses_prefix());
+        hdr.prefix = TarFormatString::new([0; PREFIX_LEN]);
+        assert!(!hdr.uses_prefix());
+    }
+
+    #[test]
+    fn test_gnu_times() {
+        let mut hdr = bytes_to_archive(include_bytes!("../tests/gnu_tar_oldgnu.tar"));
+        // GNU Tar only stores them for incremental backups.
+        assert_eq!(hdr.gnu_atime(), None);
+        assert_eq!(hdr.gnu_ctime(), None);
+
+        let mut prefix = [0; PREFIX_LEN];
+        prefix[..12].copy_from_slice(b"14124356127\0");
+        prefix[12..24].copy_from_slice(b"14124356130\0");
+        hdr.prefix = TarFormatString::new(prefix);
+        let atime = hdr.gnu_atime().unwrap().as_number::<u64>();
+        let ctime = hdr.gnu_ctime().unwrap().as_number::<u64>();
+        assert_eq!(atime, Ok(0o14124356127));
+        assert_eq!(ctime, Ok(0o14124356130));
+
+        // the same bytes are part of the name in other formats
+        hdr.magic = TarFormatString::new(*b"ustar\0");
+        hdr.version = TarFormatString::new(*b"00");
+        assert_eq!(hdr.gnu_atime(), None);
+        assert_eq!(hdr.gnu_ctime(), None);
+    }
+
+    #[test]
+    fn test_version_kind() {
+        for (archive, kind) in [
+            (
+                &include_bytes!("../tests/gnu_tar_default.tar")[..],
+                VersionKind::Gnu,
+            ),
+            (include_bytes!("../tests/gnu_tar_gnu.tar"), VersionKind::Gnu),
+            (
+                include_bytes!("../tests/gnu_tar_oldgnu.tar"),
+                VersionKind::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_gnu_long.tar"),
+                VersionKind::Gnu,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_pax.tar"),
+                VersionKind::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_pax_global.tar"),
+                VersionKind::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_posix.tar"),
+                VersionKind::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_ustar.tar"),
+                VersionKind::Ustar,
+            ),
+            (
+                include_bytes!("../tests/gnu_tar_ustar_long.tar"),
+                VersionKind::Ustar,
+            ),
+            (
+                include_bytes!("../tests/mac_tar_ustar_with_dir.tar"),
+                VersionKind::Ustar,
+            ),
+            (include_bytes!("../tests/gnu_tar_v7.tar"), VersionKind::V7),
+        ] {
+            assert_eq!(bytes_to_archive(archive).version_kind(), kind);
+        }
+
+        let mut hdr = bytes_to_archive(include_bytes!("../tests/gnu_tar_gnu.tar"));
+        hdr.version = TarFormatString::new(*b"\0 ");
+        assert_eq!(hdr.version_kind(), VersionKind::Gnu);
+        hdr.version = TarFormatString::new(*b"00");
+        assert_eq!(hdr.version_kind(), VersionKind::Unknown);
+        hdr.magic = TarFormatString::new(*b"ustar\0");
+        assert_eq!(hdr.version_kind(), VersionKind::Ustar);
+        hdr.version = TarFormatString::new(*b"01");
+        assert_eq!(hdr.version_kind(), VersionKind::Unknown);
+    }
+
     #[test]
     fn test_parse_tar_header_filename() {
         let archive = bytes_to_archive(include_bytes!("../tests/gnu_tar_default.tar"));
@@ -385,8 +1163,173 @@ mod tests {
         assert_eq!(archive.name.as_str(), Ok("bye_world_513b.txt"));
     }
 
+    #[test]
+    fn test_type_flag_set() {
+        let mut set = TypeFlagSet::new();
+        assert!(set.is_empty());
+        for typeflag in [b'5', b'L', 0, 255, b'5'] {
+            set.insert(typeflag);
+        }
+        assert!(!set.is_empty());
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(TypeFlag::DIRTYPE as u8));
+        assert!(!set.contains(b'0'));
+        assert_eq!(
+            set.iter().collect::<std::vec::Vec<_>>(),
+            [0, b'5', b'L', 255]
+        );
+        assert_eq!(std::format!("{set:?}"), "{'\\0', '5', 'L', 'ÿ'}");
+    }
+
+    #[test]
+    fn test_metadata_digest() {
+        let archive = include_bytes!("../tests/gnu_tar_default.tar");
+        let hdr = bytes_to_archive(archive);
+        // stable value
+        assert_eq!(hdr.metadata_digest(), 0x014a_27c8_d9d9_ad7d);
+
+        // Other fields don't matter.
+        let mut other = hdr;
+        other.uid = TarFormatOctal::from_number(42).unwrap();
+        other.recompute_checksum();
+        assert_eq!(other.metadata_digest(), hdr.metadata_digest());
+
+        let mut other = hdr;
+        other.mtime = TarFormatOctal::from_number(42).unwrap();
+        assert_ne!(other.metadata_digest(), hdr.metadata_digest());
+        let mut other = hdr;
+        other.typeflag = TypeFlag::DIRTYPE.into();
+        assert_ne!(other.metadata_digest(), hdr.metadata_digest());
+
+        // ustar headers that only differ in the prefix directory
+        let archive = include_bytes!("../tests/gnu_tar_ustar.tar");
+        let mut hdr = bytes_to_archive(archive);
+        hdr.prefix = TarFormatString::try_from_str("dir_a").unwrap();
+        let mut other = hdr;
+        other.prefix = TarFormatString::try_from_str("dir_b").unwrap();
+        assert!(hdr.uses_prefix() && other.uses_prefix());
+        assert_ne!(other.metadata_digest(), hdr.metadata_digest());
+    }
+
+    #[test]
+    fn test_type_flag_category() {
+        use super::EntryCategory::*;
+        for (typeflag, category) in [
+            (TypeFlag::REGTYPE, File),
+            (TypeFlag::AREGTYPE, File),
+            (TypeFlag::CONTTYPE, File),
+            (TypeFlag::DIRTYPE, Dir),
+            (TypeFlag::LINK, Link),
+            (TypeFlag::SYMTYPE, Link),
+            (TypeFlag::CHRTYPE, Device),
+            (TypeFlag::BLKTYPE, Device),
+            (TypeFlag::FIFOTYPE, Fifo),
+            (TypeFlag::XHDTYPE, Extension),
+            (TypeFlag::XGLTYPE, Extension),
+        ] {
+            assert_eq!(typeflag.category(), category);
+        }
+    }
+
+    #[test]
+    fn test_mode_to_symbolic() {
+        let mode = |bits| ModeFlags::from_bits(bits).unwrap().to_symbolic();
+        assert_eq!(&mode(0o644), b"rw-r--r--");
+        assert_eq!(&mode(0o755), b"rwxr-xr-x");
+        assert_eq!(&mode(0o000), b"---------");
+        assert_eq!(&mode(0o4755), b"rwsr-xr-x");
+        assert_eq!(&mode(0o4644), b"rwSr--r--");
+        assert_eq!(&mode(0o2750), b"rwxr-s---");
+        assert_eq!(&mode(0o2740), b"rwxr-S---");
+        assert_eq!(&mode(0o1777), b"rwxrwxrwt");
+        assert_eq!(&mode(0o1776), b"rwxrwxrwT");
+    }
+
+    #[test]
+    fn test_mode_display() {
+        let mode = |bits| std::format!("{}", ModeFlags::from_bits(bits).unwrap());
+        assert_eq!(mode(0o755), "0755");
+        assert_eq!(mode(0o644), "0644");
+        assert_eq!(mode(0o4755), "04755");
+        assert_eq!(mode(0o1777), "01777");
+        assert_eq!(mode(0o7), "0007");
+    }
+
+    #[test]
+    fn test_trailing_bytes() {
+        let archive = bytes_to_archive(include_bytes!("../tests/gnu_tar_default.tar"));
+        assert_eq!(archive.trailing_bytes(), [0; 12]);
+
+        let mut bytes = [0; BLOCKSIZE];
+        bytes[BLOCKSIZE - 4..].copy_from_slice(b"tar\0");
+        let archive = bytes_to_archive(&bytes);
+        assert_eq!(&archive.trailing_bytes()[8..], b"tar\0");
+    }
+
+    #[test]
+    fn test_type_flag_index() {
+        for (index, typeflag) in TypeFlag::ALL.into_iter().enumerate() {
+            assert_eq!(typeflag.index(), index);
+        }
+    }
+
     #[test]
     fn test_size() {
         assert_eq!(BLOCKSIZE, size_of::<PosixHeader>());
     }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_payload_block_count_base256() {
+        let mut hdr = bytes_to_archive(include_bytes!("../tests/gnu_tar_default.tar"));
+        // 10 GiB
+        hdr.size = TarFormatOctal::new([0x80, 0, 0, 0, 0, 0, 0, 0x02, 0x80, 0, 0, 0]);
+        assert_eq!(hdr.size.as_number::<u64>(), Ok(10 << 30));
+        assert_eq!(hdr.payload_block_count(), Ok((10 << 30) / BLOCKSIZE));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let mut hdr = bytes_to_archive(include_bytes!("../tests/gnu_tar_default.tar"));
+        assert_eq!(hdr.verify_checksum(), Ok(()));
+        let computed = hdr.compute_checksum();
+
+        hdr.name = TarFormatString::try_from_str("corrupt.txt").unwrap();
+        let error = hdr.verify_checksum().unwrap_err();
+        assert_eq!(error.stored, Some(computed));
+        assert_ne!(error.computed, computed);
+
+        hdr.cksum = TarFormatOctal::new(*b"garbage ");
+        assert_eq!(hdr.verify_checksum().unwrap_err().stored, None);
+    }
+
+    #[test]
+    fn test_recompute_checksum() {
+        let mut hdr = PosixHeader::from_block(&[0; BLOCKSIZE]).unwrap();
+        hdr.name = TarFormatString::try_from_str("file.txt").unwrap();
+        hdr.size = TarFormatOctal::from_number(42).unwrap();
+        hdr.typeflag = TypeFlag::REGTYPE.into();
+        hdr.magic = TarFormatString::new(*b"ustar\0");
+        hdr.version = TarFormatString::new(*b"00");
+        assert!(!hdr.checksum_is_valid());
+
+        hdr.recompute_checksum();
+        assert_eq!(hdr.verify_checksum(), Ok(()));
+        assert!(hdr.checksum_is_valid());
+        assert_eq!(hdr.cksum.as_inner().as_bytes()[6..], *b"\0 ");
+    }
+
+    #[test]
+    fn test_from_block() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        // unaligned
+        let mut bytes = [0; BLOCKSIZE + 1];
+        bytes[1..].copy_from_slice(&data[..BLOCKSIZE]);
+        let hdr = PosixHeader::from_block(&bytes[1..]).unwrap();
+        assert_eq!(hdr.name.as_str(), Ok("bye_world_513b.txt"));
+
+        assert_eq!(PosixHeader::from_block(&bytes), None);
+        assert_eq!(PosixHeader::from_block(&data[..BLOCKSIZE - 1]), None);
+        assert_eq!(PosixHeader::from_block(&[]), None);
+    }
 }
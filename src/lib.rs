@@ -40,13 +40,14 @@ SOFTWARE.
 //! This crate is simple and focuses on reading files and their content from a Tar
 //! archive. Historic basic Tar and ustar [formats](https://www.gnu.org/software/tar/manual/html_section/Formats.html)
 //! are supported. Other formats may work, but likely without all supported
-//! features. GNU Extensions such as sparse files, incremental archives, and
-//! long filename extension are not supported.
+//! features. GNU Extensions such as sparse files and incremental archives are
+//! not supported, but GNU long names and long link targets are.
 //!
 //! The maximum supported file name length is 256 characters excluding the
-//! NULL-byte (using the Tar name/prefix longname implementation of ustar). The
-//! maximum supported file size is 8GiB. Directories are supported, but only regular
-//! fields are yielded in iteration. The path is reflected in their file name.
+//! NULL-byte (using the Tar name/prefix longname implementation of ustar). File
+//! sizes beyond 8GiB are supported via the base-256 encoding of GNU Tar.
+//! Directories are supported, but only regular fields are yielded in iteration.
+//! The path is reflected in their file name.
 //!
 //! ## Use Case
 //!
@@ -76,14 +77,23 @@ SOFTWARE.
 //!
 //! This crate allows the usage of the additional Cargo build time feature `alloc`.
 //! When this is active, the crate also provides the type `TarArchive`, which owns
-//! the data on the heap. The `unstable` feature provides additional convenience
-//! only available on the nightly channel.
+//! the data on the heap, and `TarBuilder`, which creates new archives. The `unstable` feature provides additional convenience
+//! only available on the nightly channel. The `sha2` feature adds methods to
+//! compute SHA-256 digests of entries and archives. The `crc` feature adds
+//! `ArchiveEntry::payload_crc32` to compute CRC-32 checksums of entries. The `std` feature implies
+//! `alloc` and adds conveniences for host tools, such as native file paths and
+//! `TarReader`, which streams archives from a `std::io::Read` source.
+//! The `log-kv` feature attaches the block index and the name of the offending
+//! header as structured key-values to the log messages about parse problems.
+//! The `serde` feature implements `serde::Serialize` for `ArchiveEntry`,
+//! `PosixHeader`, and the field types, e.g., to dump the metadata as JSON.
 //!
 //! ## Compression (`tar.gz`)
 //!
 //! If your Tar file is compressed, e.g. by `.tar.gz`/`gzip`, you need to uncompress
 //! the bytes first (e.g. by a *gzip* library). Afterwards, this crate can read the
-//! Tar archive format from the uncompressed bytes.
+//! Tar archive format from the uncompressed bytes. Compressed data (gzip, zstd,
+//! xz, bzip2) is reported as `TarError::CompressedInput`.
 //!
 //! ## MSRV
 //!
@@ -110,25 +120,56 @@ SOFTWARE.
 #![deny(rustdoc::all)]
 
 #[cfg_attr(test, macro_use)]
-#[cfg(test)]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
 /// Each Archive Entry (either Header or Data Block) is a block of 512 bytes.
-const BLOCKSIZE: usize = 512;
+pub const BLOCKSIZE: usize = 512;
 /// Maximum filename length of the base Tar format including the terminating NULL-byte.
-const NAME_LEN: usize = 100;
+pub const NAME_LEN: usize = 100;
 /// Maximum long filename length of the base Tar format including the prefix
-const POSIX_1003_MAX_FILENAME_LEN: usize = 256;
+pub const POSIX_1003_MAX_FILENAME_LEN: usize = 256;
 /// Maximum length of the prefix in Posix tar format
-const PREFIX_LEN: usize = 155;
+pub const PREFIX_LEN: usize = 155;
+
+/// Logs a problem with the header at the given block index. With the `log-kv`
+/// feature, the block index and the name of the header are attached as
+/// structured key-values.
+macro_rules! log_header {
+    ($level:ident, $block_index:expr, $hdr:expr, $($arg:tt)+) => {{
+        #[cfg(feature = "log-kv")]
+        log::$level!(
+            block_index = $block_index,
+            name = $hdr.name.as_str().unwrap_or_default();
+            $($arg)+
+        );
+        #[cfg(not(feature = "log-kv"))]
+        {
+            let _ = (&$block_index, &$hdr);
+            log::$level!($($arg)+);
+        }
+    }};
+}
 
 mod archive;
+mod block_source;
 mod header;
+mod pax;
+#[cfg(feature = "std")]
+mod reader;
 mod tar_format_types;
+#[cfg(feature = "alloc")]
+mod writer;
 
 pub use archive::*;
+pub use block_source::*;
 pub use header::*;
+pub use pax::*;
+#[cfg(feature = "std")]
+pub use reader::*;
 pub use tar_format_types::*;
+#[cfg(feature = "alloc")]
+pub use writer::*;
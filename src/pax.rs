@@ -0,0 +1,354 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for PAX extended headers (POSIX.1-2001). They carry additional
+//! `key=value` records either for the following entry (typeflag `x`) or for
+//! all following entries (typeflag `g`).
+
+use core::str::{from_utf8, Utf8Error};
+use log::warn;
+
+/// A single `key=value` record of a PAX extended header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PaxRecord<'a> {
+    key: &'a str,
+    value: &'a [u8],
+}
+
+impl<'a> PaxRecord<'a> {
+    /// The key of the record, such as `path` or `mtime`.
+    #[must_use]
+    pub const fn key(&self) -> &'a str {
+        self.key
+    }
+
+    /// The raw value of the record.
+    #[must_use]
+    pub const fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// The value of the record as string slice, if it is valid UTF-8.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn value_as_str(&self) -> Result<&'a str, Utf8Error> {
+        from_utf8(self.value)
+    }
+}
+
+/// Iterator over the records of the payload of a PAX extended header.
+///
+/// Each record has the format `"%d %s=%s\n"`, where the decimal number is the
+/// length of the whole record, including the number itself and the newline.
+///
+/// Malformed records, e.g., with a length that doesn't match the record, are
+/// logged and skipped up to the next newline.
+#[derive(Clone, Debug)]
+pub struct PaxRecordIterator<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PaxRecordIterator<'a> {
+    /// Creates a new iterator over the records in the given payload.
+    #[must_use]
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Parses the record at the beginning of `data`. Returns the record and
+    /// its length in bytes.
+    fn parse_record(data: &'a [u8]) -> Option<(PaxRecord<'a>, usize)> {
+        let space_index = memchr::memchr(b' ', data)?;
+        let len_digits = &data[..space_index];
+        if len_digits.is_empty() || !len_digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let len = from_utf8(len_digits).ok()?.parse::<usize>().ok()?;
+
+        // The record must at least hold the length, the space, `=`, and the
+        // newline.
+        if len < space_index + 3 || len > data.len() {
+            return None;
+        }
+        let (newline, record) = data[space_index + 1..len].split_last()?;
+        if *newline != b'\n' {
+            return None;
+        }
+        let equals_index = memchr::memchr(b'=', record)?;
+        let key = from_utf8(&record[..equals_index]).ok()?;
+        if key.is_empty() {
+            return None;
+        }
+        let value = &record[equals_index + 1..];
+        Some((PaxRecord { key, value }, len))
+    }
+}
+
+impl<'a> Iterator for PaxRecordIterator<'a> {
+    type Item = PaxRecord<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A loop instead of recursion, as an untrusted header may contain
+        // arbitrarily many malformed records.
+        loop {
+            // Some producers pad the payload with NULL bytes.
+            if self.data.first().map_or(true, |x| *x == 0) {
+                return None;
+            }
+
+            if let Some((record, len)) = Self::parse_record(self.data) {
+                self.data = &self.data[len..];
+                return Some(record);
+            }
+            // The length can't be trusted, so continue after the next
+            // newline.
+            let len = memchr::memchr(b'\n', self.data).map_or(self.data.len(), |i| i + 1);
+            warn!("Skipping malformed PAX record: {:?}", &self.data[..len]);
+            self.data = &self.data[len..];
+        }
+    }
+}
+
+/// The PAX extended header records that apply to an entry.
+///
+/// This combines the records of the extended header of the entry (typeflag
+/// `x`) with the records of the most recent global extended header (typeflag
+/// `g`). The records of the entry's own header take precedence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PaxHeader<'a> {
+    local: &'a [u8],
+    global: &'a [u8],
+}
+
+impl<'a> PaxHeader<'a> {
+    /// Creates a new header from the payloads of the extended headers.
+    /// Returns `None`, if there are no extended headers.
+    pub(crate) fn new(local: Option<&'a [u8]>, global: Option<&'a [u8]>) -> Option<Self> {
+        (local.is_some() || global.is_some()).then(|| Self {
+            local: local.unwrap_or_default(),
+            global: global.unwrap_or_default(),
+        })
+    }
+
+    /// Returns the records of the extended header of the entry.
+    #[must_use]
+    pub const fn local_records(&self) -> PaxRecordIterator<'a> {
+        PaxRecordIterator::new(self.local)
+    }
+
+    /// Returns the records of the most recent global extended header.
+    #[must_use]
+    pub const fn global_records(&self) -> PaxRecordIterator<'a> {
+        PaxRecordIterator::new(self.global)
+    }
+
+    /// Returns the effective records, i.e., all records of the entry's own
+    /// extended header followed by the global records that it doesn't
+    /// override.
+    pub fn records(&self) -> impl Iterator<Item = PaxRecord<'a>> {
+        let local = self.local_records();
+        let overridden = move |key: &str| local.clone().any(|record| record.key() == key);
+        self.local_records().chain(
+            self.global_records()
+                .filter(move |record| !overridden(record.key())),
+        )
+    }
+
+    /// Returns the value of the effective record with the given key. If a key
+    /// occurs multiple times in a header, the last record wins.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&'a [u8]> {
+        let find = |records: PaxRecordIterator<'a>| {
+            records
+                .filter(|record| record.key() == key)
+                .last()
+                .map(|record| record.value())
+        };
+        find(self.local_records()).or_else(|| find(self.global_records()))
+    }
+
+    /// The `path` record, which overrides the filename of the entry.
+    #[must_use]
+    pub fn path(&self) -> Option<&'a str> {
+        from_utf8(self.get("path")?).ok()
+    }
+
+    /// The `linkpath` record, which overrides the link target of the entry.
+    #[must_use]
+    pub fn linkpath(&self) -> Option<&'a str> {
+        from_utf8(self.get("linkpath")?).ok()
+    }
+
+    /// The `size` record, which overrides the size of the entry. Returns
+    /// `None`, if the value isn't a decimal number.
+    #[must_use]
+    pub fn size(&self) -> Option<u64> {
+        self.decimal("size")
+    }
+
+    /// The `uid` record, which overrides the user ID of the entry. Returns
+    /// `None`, if the value isn't a decimal number.
+    #[must_use]
+    pub fn uid(&self) -> Option<u64> {
+        self.decimal("uid")
+    }
+
+    /// The `gid` record, which overrides the group ID of the entry. Returns
+    /// `None`, if the value isn't a decimal number.
+    #[must_use]
+    pub fn gid(&self) -> Option<u64> {
+        self.decimal("gid")
+    }
+
+    /// The whole seconds of the `atime` record, i.e., the access time in
+    /// seconds since the Unix epoch. The fractional part is ignored. Returns
+    /// `None`, if the value isn't a valid decimal number.
+    #[must_use]
+    pub fn atime(&self) -> Option<i64> {
+        self.seconds("atime")
+    }
+
+    /// The whole seconds of the `mtime` record, which overrides the
+    /// modification time of the entry. See [`Self::atime`].
+    #[must_use]
+    pub fn mtime(&self) -> Option<i64> {
+        self.seconds("mtime")
+    }
+
+    /// Parses the value of the record as unsigned decimal number.
+    fn decimal(&self, key: &str) -> Option<u64> {
+        let value = from_utf8(self.get(key)?).ok()?;
+        value
+            .bytes()
+            .all(|byte| byte.is_ascii_digit())
+            .then(|| value.parse().ok())
+            .flatten()
+    }
+
+    /// Parses the whole seconds of a timestamp record.
+    fn seconds(&self, key: &str) -> Option<i64> {
+        let value = from_utf8(self.get(key)?).ok()?;
+        let (seconds, fraction) = value.split_once('.').unwrap_or((value, "0"));
+        if !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        seconds.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_record_iterator() {
+        let data = b"30 mtime=1633330466.888204366\n17 comment=a=b c\n\0\0\0";
+        let records = PaxRecordIterator::new(data).collect::<Vec<_>>();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].key(), "mtime");
+        assert_eq!(records[0].value_as_str(), Ok("1633330466.888204366"));
+        assert_eq!(records[1].key(), "comment");
+        assert_eq!(records[1].value(), b"a=b c");
+    }
+
+    #[test]
+    fn test_malformed_length_prefix() {
+        fn records(data: &[u8]) -> Vec<&str> {
+            PaxRecordIterator::new(data)
+                .map(|record| record.key())
+                .collect()
+        }
+        // too long
+        assert_eq!(records(b"99 path=foo\n12 uid=1000\n"), ["uid"]);
+        assert_eq!(records(b"13 path=foo\n"), [] as [&str; 0]);
+        // too short
+        assert_eq!(records(b"5 path=foo\n12 uid=1000\n"), ["uid"]);
+        assert_eq!(records(b"0 path=foo\n"), [] as [&str; 0]);
+        // not numeric
+        assert_eq!(records(b"1x path=foo\n12 uid=1000\n"), ["uid"]);
+        assert_eq!(records(b"-12 path=foo\n12 uid=1000\n"), ["uid"]);
+        assert_eq!(records(b" path=foo\n12 uid=1000\n"), ["uid"]);
+        // overflow
+        assert_eq!(
+            records(b"99999999999999999999999 path=foo\n12 uid=1000\n"),
+            ["uid"]
+        );
+        // no space, no key, no newline
+        assert_eq!(records(b"12\n12 uid=1000\n"), ["uid"]);
+        assert_eq!(records(b"8 =foo\n12 uid=1000\n"), ["uid"]);
+        assert_eq!(records(b"12 uid=1000"), [] as [&str; 0]);
+        // many malformed records must not overflow the stack
+        let mut data = vec![b'\n'; 64 * 1024];
+        data.extend_from_slice(b"12 uid=1000\n");
+        assert_eq!(records(&data), ["uid"]);
+    }
+
+    #[test]
+    fn test_typed_records() {
+        let header = PaxHeader::new(
+            Some(b"16 path=a/b.txt\n16 size=1234567\n30 atime=1633330466.888204366\n"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(header.path(), Some("a/b.txt"));
+        assert_eq!(header.size(), Some(1234567));
+        assert_eq!(header.atime(), Some(1633330466));
+        assert_eq!(header.mtime(), None);
+
+        let header = PaxHeader::new(
+            Some(b"16 linkpath=a/b\n12 uid=1000\n12 gid=1001\n20 mtime=1633330466\n"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(header.linkpath(), Some("a/b"));
+        assert_eq!(header.uid(), Some(1000));
+        assert_eq!(header.gid(), Some(1001));
+        assert_eq!(header.mtime(), Some(1633330466));
+
+        let header =
+            PaxHeader::new(Some(b"12 size=-12\n12 atime=-5\n15 atime=1.5e3\n"), None).unwrap();
+        assert_eq!(header.path(), None);
+        assert_eq!(header.size(), None);
+        assert_eq!(header.atime(), None);
+        let header = PaxHeader::new(Some(b"12 atime=-5\n"), None).unwrap();
+        assert_eq!(header.atime(), Some(-5));
+    }
+
+    #[test]
+    fn test_header_precedence() {
+        let header = PaxHeader::new(
+            Some(b"14 path=local\n12 uid=1000\n12 uid=1001\n"),
+            Some(b"15 path=global\n12 comment=\n"),
+        )
+        .unwrap();
+        assert_eq!(header.get("path"), Some(b"local".as_slice()));
+        assert_eq!(header.get("uid"), Some(b"1001".as_slice()));
+        assert_eq!(header.get("comment"), Some(b"".as_slice()));
+        assert_eq!(header.get("gid"), None);
+
+        let keys = header.records().map(|r| r.key()).collect::<Vec<_>>();
+        assert_eq!(keys, ["path", "uid", "uid", "comment"]);
+
+        assert_eq!(PaxHeader::new(None, None), None);
+    }
+}
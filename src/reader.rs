@@ -0,0 +1,207 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`TarReader`], which reads Tar archives from a stream instead
+//! of a slice in memory. This is only available with the `std` feature.
+
+use crate::{block_padding, PosixHeader, BLOCKSIZE};
+use std::io::{self, Read};
+
+/// Reads the entries of a Tar archive from a [`Read`] source, e.g., a file
+/// or a slow block device, one header at a time.
+///
+/// Unlike [`crate::TarArchiveRef`], the archive doesn't need to be in memory.
+/// Each call to [`TarReader::next_entry`] returns a [`TarReaderEntry`], whose
+/// data can be read with [`Read`]. Data that isn't read is skipped, when the
+/// next entry is requested.
+///
+/// Entries of all types are returned, including directories, links, PAX
+/// extended headers, and GNU long name records. They are not applied to the
+/// following entries.
+///
+/// This is only available with the `std` feature of this crate.
+#[derive(Debug)]
+pub struct TarReader<R: Read> {
+    inner: R,
+    /// Bytes of the data of the current entry that were not read yet.
+    data_left: u64,
+    /// Bytes of padding after the data of the current entry.
+    padding: u64,
+    is_finished: bool,
+}
+
+impl<R: Read> TarReader<R> {
+    /// Creates a new reader that reads the archive from the beginning of
+    /// `inner`.
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            data_left: 0,
+            padding: 0,
+            is_finished: false,
+        }
+    }
+
+    /// Skips the rest of the current entry and reads the next header.
+    ///
+    /// Returns `None` at the terminating zero blocks or at the end of the
+    /// source. Errors of the source are returned, as well as
+    /// [`io::ErrorKind::InvalidData`] for unparsable size fields and
+    /// [`io::ErrorKind::UnexpectedEof`] for incomplete blocks.
+    pub fn next_entry(&mut self) -> io::Result<Option<TarReaderEntry<'_, R>>> {
+        if self.is_finished {
+            return Ok(None);
+        }
+        let skip = self.data_left + self.padding;
+        let skipped = io::copy(&mut (&mut self.inner).take(skip), &mut io::sink())?;
+        if skipped < skip {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        (self.data_left, self.padding) = (0, 0);
+
+        let mut block = [0; BLOCKSIZE];
+        let len = read_full(&mut self.inner, &mut block)?;
+        if len == 0 {
+            self.is_finished = true;
+            return Ok(None);
+        }
+        if len < BLOCKSIZE {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let header = PosixHeader::from_block(&block).unwrap();
+        if header.is_zero_block() {
+            self.is_finished = true;
+            return Ok(None);
+        }
+
+        if header.typeflag.has_payload() {
+            let size = header
+                .size
+                .as_number::<u64>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.data_left = size;
+            self.padding = block_padding(size) as u64;
+        }
+        Ok(Some(TarReaderEntry {
+            reader: self,
+            header,
+        }))
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Reads until the buffer is full or the end of the source is reached and
+/// returns the number of bytes read.
+fn read_full(source: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match source.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+/// An entry of a [`TarReader`]. Its data can be read with [`Read`], which
+/// stops at the end of the data of the entry.
+#[derive(Debug)]
+pub struct TarReaderEntry<'r, R: Read> {
+    reader: &'r mut TarReader<R>,
+    header: PosixHeader,
+}
+
+impl<R: Read> TarReaderEntry<'_, R> {
+    /// The header of the entry.
+    #[must_use]
+    pub const fn header(&self) -> &PosixHeader {
+        &self.header
+    }
+
+    /// Number of bytes of data that were not read yet.
+    #[must_use]
+    pub const fn remaining_bytes(&self) -> u64 {
+        self.reader.data_left
+    }
+}
+
+impl<R: Read> Read for TarReaderEntry<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = usize::try_from(self.reader.data_left).unwrap_or(usize::MAX);
+        let max = buf.len().min(max);
+        let len = self.reader.inner.read(&mut buf[..max])?;
+        if len == 0 && max > 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.reader.data_left -= len as u64;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::string::String;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_reader() {
+        let data = include_bytes!("../tests/gnu_tar_default.tar");
+        let mut reader = TarReader::new(Cursor::new(data));
+
+        // only read a part of the first entry
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert_eq!(entry.header().name.as_str(), Ok("bye_world_513b.txt"));
+        let mut start = [0; 3];
+        entry.read_exact(&mut start).unwrap();
+        assert_eq!(entry.remaining_bytes(), 510);
+
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert_eq!(entry.header().name.as_str(), Ok("hello_world_513b.txt"));
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        assert_eq!(content.len(), 513);
+
+        let mut entry = reader.next_entry().unwrap().unwrap();
+        assert_eq!(entry.header().name.as_str(), Ok("hello_world.txt"));
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "Hello World\n");
+
+        assert!(reader.next_entry().unwrap().is_none());
+        assert!(reader.next_entry().unwrap().is_none());
+
+        // truncated data
+        let mut reader = TarReader::new(Cursor::new(&data[..1000]));
+        assert!(reader.next_entry().unwrap().is_some());
+        let error = reader.next_entry().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
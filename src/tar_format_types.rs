@@ -1,6 +1,8 @@
 #![allow(unused_imports)]
 
-use core::fmt::{Debug, Formatter};
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
 use core::num::ParseIntError;
 use core::ptr::copy_nonoverlapping;
 use core::str::{from_utf8, Utf8Error};
@@ -13,12 +15,40 @@ use num_traits::Num;
 /// An optionally null terminated string. The contents are either:
 /// 1. A fully populated string with no null termination or
 /// 2. A partially populated string where the unused bytes are zero.
-#[derive(Copy, Clone, PartialEq, Eq)]
+///
+/// Equality, hashing, and ordering only consider the bytes until the first
+/// NULL byte, i.e., what [`TarFormatString::as_str`] returns. Strings are
+/// ordered by the byte values of this content. Thus, the strings can be
+/// used as keys of a `HashSet` or a `BTreeSet`, e.g., to deduplicate entries
+/// by name.
+#[derive(Copy, Clone)]
 #[repr(C)]
 pub struct TarFormatString<const N: usize> {
     bytes: [u8; N],
 }
 
+/// The data doesn't fit into a fixed-size field of a Tar header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    /// Number of bytes that were requested.
+    pub requested: usize,
+    /// Number of bytes that are available.
+    pub available: usize,
+}
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} bytes requested but only {} bytes available",
+            self.requested, self.available
+        )
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for CapacityError {}
+
 /// A Tar format string is a fixed length byte array containing UTF-8 bytes.
 /// This string will be null terminated if it doesn't fill the entire array.
 impl<const N: usize> TarFormatString<N> {
@@ -31,6 +61,42 @@ impl<const N: usize> TarFormatString<N> {
         Self { bytes }
     }
 
+    /// Creates a new string from the bytes of `str`. The string is NULL
+    /// terminated, if it doesn't fill the entire array. Returns an error, if
+    /// `str` is longer than `N` bytes.
+    pub fn try_from_str(str: &str) -> Result<Self, CapacityError> {
+        if str.len() > N {
+            return Err(CapacityError {
+                requested: str.len(),
+                available: N,
+            });
+        }
+        let mut bytes = [0; N];
+        bytes[..str.len()].copy_from_slice(str.as_bytes());
+        Ok(Self::new(bytes))
+    }
+
+    /// Like [`Self::try_from_str`] but truncates `str` to the capacity
+    /// instead of returning an error. This is meant for best-effort header
+    /// fields, such as user and group names, where a clipped value is
+    /// acceptable.
+    ///
+    /// The string is truncated at a character boundary, so that it stays
+    /// valid UTF-8. Truncation is logged as warning.
+    #[must_use]
+    pub fn from_str_truncating(str: &str) -> Self {
+        let mut len = str.len().min(N);
+        while !str.is_char_boundary(len) {
+            len -= 1;
+        }
+        if len < str.len() {
+            log::warn!("Truncating {str:?} to {len} bytes");
+        }
+        let mut bytes = [0; N];
+        bytes[..len].copy_from_slice(&str.as_bytes()[..len]);
+        Self::new(bytes)
+    }
+
     /// True if the is string empty (ignoring NULL bytes).
     #[must_use]
     pub const fn is_empty(&self) -> bool {
@@ -44,13 +110,52 @@ impl<const N: usize> TarFormatString<N> {
         memchr::memchr(0, &self.bytes).unwrap_or(N)
     }
 
+    /// Returns the bytes until the first NULL byte.
+    fn content(&self) -> &[u8] {
+        &self.bytes[..self.size()]
+    }
+
+    /// Returns all bytes of the underlying array.
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    pub(crate) const fn as_bytes(&self) -> &[u8; N] {
+        &self.bytes
+    }
+
     /// Returns a str ref without terminating or intermediate NULL bytes. The
     /// string is truncated at the first NULL byte, in case not the full length
-    /// was used.
+    /// was used. All other bytes are preserved verbatim, including stray
+    /// line breaks.
     pub fn as_str(&self) -> Result<&str, Utf8Error> {
         from_utf8(&self.bytes[0..self.size()])
     }
 
+    /// Returns all bytes of the field, including NULL bytes and any data
+    /// after them. This is what was stored in the archive.
+    #[must_use]
+    pub const fn raw_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Like [`Self::as_str`] but only trims trailing NULL bytes, i.e.,
+    /// interior NULL bytes and the data after them are preserved. This helps
+    /// to reconstruct what writers stored that leave junk after the
+    /// terminator.
+    ///
+    /// If the bytes aren't valid UTF-8, the longest valid prefix is returned.
+    #[must_use]
+    pub fn as_str_lossy_full(&self) -> &str {
+        let len = self
+            .bytes
+            .iter()
+            .rposition(|&b| b != 0)
+            .map_or(0, |i| i + 1);
+        let bytes = &self.bytes[..len];
+        from_utf8(bytes).unwrap_or_else(|e| {
+            // The prefix is valid by definition.
+            from_utf8(&bytes[..e.valid_up_to()]).unwrap()
+        })
+    }
+
     /// Wrapper around [`Self::as_str`] that stops as soon as the first space
     /// is found. This is necessary to properly parse certain Tar-style encoded
     /// numbers. Some ustar implementations pad spaces which prevents the proper
@@ -62,11 +167,35 @@ impl<const N: usize> TarFormatString<N> {
         })
     }
 
-    /// Append to end of string. Panics if there is not enough capacity.
+    /// Like [`Self::as_str_until_first_space`] but also trims the line breaks
+    /// that text-mode file transfers may insert, which yields the digits of a
+    /// numeric field.
+    pub(crate) fn as_digits(&self) -> Result<&str, Utf8Error> {
+        self.as_str_until_first_space()
+            .map(|str| str.trim_matches(|c| c == '\r' || c == '\n'))
+    }
+
+    /// Append to end of string. Panics if there is not enough capacity. See
+    /// [`Self::try_append`] for a fallible alternative.
     pub fn append<const S: usize>(&mut self, other: &TarFormatString<S>) {
+        if self.try_append(other).is_err() {
+            panic!("Result to long for capacity {}", N);
+        }
+    }
+
+    /// Like [`Self::append`] but returns an error instead of panicking, if
+    /// there is not enough capacity. The string is left unchanged in that
+    /// case.
+    pub fn try_append<const S: usize>(
+        &mut self,
+        other: &TarFormatString<S>,
+    ) -> Result<(), CapacityError> {
         let resulting_length = self.size() + other.size();
         if resulting_length > N {
-            panic!("Result to long for capacity {}", N);
+            return Err(CapacityError {
+                requested: resulting_length,
+                available: N,
+            });
         }
 
         unsafe {
@@ -78,6 +207,53 @@ impl<const N: usize> TarFormatString<N> {
         if resulting_length < N {
             self.bytes[resulting_length] = 0;
         }
+        Ok(())
+    }
+
+    /// Like [`Self::append`] but appends the bytes of `str` and returns an
+    /// error instead of panicking, if there is not enough capacity. The
+    /// string is left unchanged in that case.
+    pub fn append_str(&mut self, str: &str) -> Result<(), CapacityError> {
+        let size = self.size();
+        let resulting_length = size + str.len();
+        if resulting_length > N {
+            return Err(CapacityError {
+                requested: resulting_length,
+                available: N,
+            });
+        }
+
+        self.bytes[size..resulting_length].copy_from_slice(str.as_bytes());
+        if resulting_length < N {
+            self.bytes[resulting_length] = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> PartialEq for TarFormatString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.content() == other.content()
+    }
+}
+
+impl<const N: usize> Eq for TarFormatString<N> {}
+
+impl<const N: usize> Hash for TarFormatString<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.content().hash(state);
+    }
+}
+
+impl<const N: usize> PartialOrd for TarFormatString<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for TarFormatString<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.content().cmp(other.content())
     }
 }
 
@@ -94,6 +270,18 @@ impl<const N: usize> Debug for TarFormatString<N> {
     }
 }
 
+/// Serializes the string with [`TarFormatString::as_str`]. Strings that
+/// aren't valid UTF-8 are serialized as bytes.
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for TarFormatString<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.as_str() {
+            Ok(str) => serializer.serialize_str(str),
+            Err(_) => serializer.serialize_bytes(&self.bytes[..self.size()]),
+        }
+    }
+}
+
 /// A number with a specified base. Trailing spaces in the string are ignored.
 #[derive(Copy, Clone, PartialEq, Eq)]
 #[repr(C)]
@@ -110,19 +298,41 @@ pub struct TarFormatOctal<const N: usize>(TarFormatNumber<N, 8>);
 pub struct TarFormatDecimal<const N: usize>(TarFormatNumber<N, 10>);
 
 impl<const N: usize, const R: u32> TarFormatNumber<N, R> {
-    #[cfg(test)]
-    const fn new(bytes: [u8; N]) -> Self {
+    pub(crate) const fn new(bytes: [u8; N]) -> Self {
         Self(TarFormatString::<N> { bytes })
     }
 
+    /// Parses the number. Trailing spaces and stray line breaks, e.g., from
+    /// text-mode file transfers, are ignored.
     pub fn as_number<T>(&self) -> core::result::Result<T, T::FromStrRadixErr>
     where
         T: num_traits::Num,
     {
-        let str = self.0.as_str_until_first_space().unwrap_or("0");
+        let str = self.0.as_digits().unwrap_or("0");
         T::from_str_radix(str, R)
     }
 
+    /// Compatibility aid for archives whose producers wrote a number in the
+    /// wrong base, e.g., decimal digits into an octal field. The declared
+    /// radix takes precedence: Only if the field can't be parsed with it, it
+    /// is parsed with `fallback_radix`. If both fail, the error of the
+    /// declared radix is returned.
+    ///
+    /// Note that a value whose digits are valid in both bases, such as `644`,
+    /// is always parsed with the declared radix.
+    pub fn as_number_with_fallback<T>(
+        &self,
+        fallback_radix: u32,
+    ) -> core::result::Result<T, T::FromStrRadixErr>
+    where
+        T: num_traits::Num,
+    {
+        self.as_number().or_else(|err| {
+            let str = self.0.as_digits().unwrap_or("0");
+            T::from_str_radix(str, fallback_radix).map_err(|_| err)
+        })
+    }
+
     /// Returns the underlying [`TarFormatString`].
     #[must_use]
     pub const fn as_inner(&self) -> &TarFormatString<N> {
@@ -131,18 +341,49 @@ impl<const N: usize, const R: u32> TarFormatNumber<N, R> {
 }
 
 impl<const N: usize, const R: u32> Debug for TarFormatNumber<N, R> {
+    /// Shows the parsed number and the raw bytes. Bytes that aren't printable
+    /// ASCII, as found in corrupt archives, are escaped.
     fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
         let sub_array = &self.0.bytes[0..self.0.size()];
         match self.as_number::<u64>() {
-            Err(msg) => write!(f, "{} [{}]", msg, from_utf8(sub_array).unwrap()),
-            Ok(val) => write!(f, "{} [{}]", val, from_utf8(sub_array).unwrap()),
+            Err(msg) => write!(f, "{} [{}]", msg, sub_array.escape_ascii()),
+            Ok(val) => write!(f, "{} [{}]", val, sub_array.escape_ascii()),
         }
     }
 }
 
+/// Serializes the parsed number or `None`, if it can't be parsed.
+#[cfg(feature = "serde")]
+impl<const N: usize, const R: u32> serde::Serialize for TarFormatNumber<N, R> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_number::<u64>().ok().serialize(serializer)
+    }
+}
+
+/// Serializes the parsed number or `None`, if it can't be parsed. Negative
+/// values are possible in the base-256 encoding.
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for TarFormatOctal<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_number::<i64>().ok().serialize(serializer)
+    }
+}
+
+/// Serializes the parsed number or `None`, if it can't be parsed.
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for TarFormatDecimal<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
 impl<const N: usize> Debug for TarFormatOctal<N> {
     fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-        self.0.fmt(f)
+        let sub_array = &self.0 .0.bytes[0..self.0 .0.size()];
+        match self.as_number::<i128>() {
+            Err(msg) => write!(f, "{} [{}]", msg, sub_array.escape_ascii()),
+            Ok(val) => write!(f, "{} [{}]", val, sub_array.escape_ascii()),
+        }
     }
 }
 
@@ -160,6 +401,17 @@ impl<const N: usize> TarFormatDecimal<N> {
         self.0.as_number::<T>()
     }
 
+    /// See [`TarFormatNumber::as_number_with_fallback`].
+    pub fn as_number_with_fallback<T>(
+        &self,
+        fallback_radix: u32,
+    ) -> core::result::Result<T, T::FromStrRadixErr>
+    where
+        T: num_traits::Num,
+    {
+        self.0.as_number_with_fallback::<T>(fallback_radix)
+    }
+
     /// Returns the underlying [`TarFormatString`].
     #[must_use]
     pub const fn as_inner(&self) -> &TarFormatString<N> {
@@ -168,11 +420,89 @@ impl<const N: usize> TarFormatDecimal<N> {
 }
 
 impl<const N: usize> TarFormatOctal<N> {
+    pub(crate) const fn new(bytes: [u8; N]) -> Self {
+        Self(TarFormatNumber::new(bytes))
+    }
+
+    /// Encodes the value as zero-padded octal number that is terminated by a
+    /// NULL byte, as GNU Tar does. Values that need more than `N - 1` digits
+    /// are stored in the base-256 encoding of GNU Tar instead, i.e., the
+    /// first byte is `0x80` and the remaining bytes hold the big-endian value.
+    ///
+    /// Returns `None`, if the value doesn't fit into `N - 1` bytes.
+    pub(crate) fn from_number(value: u64) -> Option<Self> {
+        let mut bytes = [0; N];
+        let digits = N - 1;
+        let max_octal = 8_u64.checked_pow(digits as u32).map_or(u64::MAX, |x| x - 1);
+        if value <= max_octal {
+            let mut remaining = value;
+            for byte in bytes[..digits].iter_mut().rev() {
+                *byte = b'0' + (remaining % 8) as u8;
+                remaining /= 8;
+            }
+        } else {
+            let be_bytes = value.to_be_bytes();
+            let used_bytes = be_bytes.iter().skip_while(|x| **x == 0).count();
+            if used_bytes > digits {
+                return None;
+            }
+            bytes[0] = 0x80;
+            bytes[N - used_bytes..].copy_from_slice(&be_bytes[be_bytes.len() - used_bytes..]);
+        }
+        Some(Self::new(bytes))
+    }
+
+    /// Parses the number. Besides octal digits, this supports the base-256
+    /// encoding of GNU Tar for values that don't fit into the octal digits,
+    /// such as sizes of more than 8 GiB or negative timestamps.
     pub fn as_number<T>(&self) -> core::result::Result<T, T::FromStrRadixErr>
     where
         T: num_traits::Num,
     {
-        self.0.as_number::<T>()
+        if self.is_base256() {
+            parse_decimal(self.base256_value())
+        } else {
+            self.0.as_number::<T>()
+        }
+    }
+
+    /// See [`TarFormatNumber::as_number_with_fallback`]. Values in the
+    /// base-256 encoding are never parsed with the fallback radix.
+    pub fn as_number_with_fallback<T>(
+        &self,
+        fallback_radix: u32,
+    ) -> core::result::Result<T, T::FromStrRadixErr>
+    where
+        T: num_traits::Num,
+    {
+        if self.is_base256() {
+            self.as_number()
+        } else {
+            self.0.as_number_with_fallback::<T>(fallback_radix)
+        }
+    }
+
+    /// Decodes the base-256 encoding of GNU Tar. The remaining bits of the
+    /// first byte and all following bytes form a big-endian two's
+    /// complement number, i.e., positive values start with `0x80` and
+    /// negative values with `0xff`. Returns `None` on overflow.
+    fn base256_value(&self) -> Option<i128> {
+        let bytes = &self.0 .0.bytes;
+        let mut value = i128::from(bytes[0] & 0x3f);
+        for byte in &bytes[1..] {
+            value = value.checked_mul(256)?.checked_add(i128::from(*byte))?;
+        }
+        if bytes[0] & 0x40 != 0 {
+            let bits = 6 + 8 * u32::try_from(N - 1).ok()?;
+            value = value.checked_sub(1_i128.checked_shl(bits)?)?;
+        }
+        Some(value)
+    }
+
+    /// Whether the field uses the base-256 encoding of GNU Tar, which is
+    /// marked by the highest bit of the first byte.
+    pub(crate) const fn is_base256(&self) -> bool {
+        N > 0 && self.0 .0.bytes[0] & 0x80 != 0
     }
 
     /// Returns the underlying [`TarFormatString`].
@@ -182,9 +512,37 @@ impl<const N: usize> TarFormatOctal<N> {
     }
 }
 
+/// Converts a decoded value into the target type. This goes through the
+/// decimal representation to report values that don't fit into `T` with the
+/// error type of [`Num::from_str_radix`].
+fn parse_decimal<T: Num>(value: Option<i128>) -> Result<T, T::FromStrRadixErr> {
+    // more than u128::MAX
+    const OVERFLOW: &str = "9999999999999999999999999999999999999999";
+    let Some(value) = value else {
+        return T::from_str_radix(OVERFLOW, 10);
+    };
+    // i128::MIN has 39 digits plus the sign
+    let mut buf = [0; 40];
+    let mut pos = buf.len();
+    let mut magnitude = value.unsigned_abs();
+    loop {
+        pos -= 1;
+        buf[pos] = b'0' + (magnitude % 10) as u8;
+        magnitude /= 10;
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        pos -= 1;
+        buf[pos] = b'-';
+    }
+    T::from_str_radix(from_utf8(&buf[pos..]).unwrap_or_default(), 10)
+}
+
 #[cfg(test)]
 mod tar_format_string_tests {
-    use super::TarFormatString;
+    use super::{CapacityError, TarFormatString};
 
     use core::mem::size_of_val;
 
@@ -215,6 +573,92 @@ mod tar_format_string_tests {
         assert_eq!(s.as_str(), Ok("A"));
     }
 
+    #[test]
+    fn test_raw_bytes() {
+        // a fully populated name without NULL byte
+        let name = TarFormatString::new([b'a'; 100]);
+        assert_eq!(name.size(), 100);
+        assert_eq!(name.as_str().unwrap().len(), 100);
+        assert_eq!(name.as_str_lossy_full(), "a".repeat(100));
+        assert_eq!(name.raw_bytes(), [b'a'; 100]);
+
+        // junk after the terminator
+        let s = TarFormatString::new(*b"A\0B\0\0");
+        assert_eq!(s.as_str(), Ok("A"));
+        assert_eq!(s.as_str_lossy_full(), "A\0B");
+        assert_eq!(s.raw_bytes(), b"A\0B\0\0");
+
+        let s = TarFormatString::new(*b"AB\xff\0");
+        assert_eq!(s.as_str_lossy_full(), "AB");
+        assert_eq!(TarFormatString::new([0; 4]).as_str_lossy_full(), "");
+    }
+
+    #[test]
+    fn test_hash_ord() {
+        use std::collections::{BTreeSet, HashSet};
+
+        // equal content, but different junk after the terminator
+        let a = TarFormatString::new(*b"foo\0\0\0");
+        let b = TarFormatString::new(*b"foo\0ba");
+        assert_eq!(a, b);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(a));
+        assert!(!set.insert(b));
+        assert!(set.insert(TarFormatString::new(*b"bar\0\0\0")));
+        assert_eq!(set.len(), 2);
+
+        let names = ["foo", "bar", "foo", "foobar", "Foo"]
+            .map(|name| TarFormatString::<8>::try_from_str(name).unwrap());
+        let sorted = names
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|name| name.as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, ["Foo", "bar", "foo", "foobar"]);
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let s = TarFormatString::<3>::try_from_str("AB").unwrap();
+        assert_eq!(s.size(), 2);
+        assert_eq!(s.as_str(), Ok("AB"));
+
+        let s = TarFormatString::<3>::try_from_str("ABC").unwrap();
+        assert_eq!(s.size(), 3);
+        assert_eq!(s.as_str(), Ok("ABC"));
+
+        assert_eq!(
+            TarFormatString::<3>::try_from_str("ABCD"),
+            Err(CapacityError {
+                requested: 4,
+                available: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_str_truncating() {
+        // N - 1, N, and N + 1 bytes
+        let s = TarFormatString::<3>::from_str_truncating("AB");
+        assert_eq!((s.size(), s.as_str()), (2, Ok("AB")));
+        let s = TarFormatString::<3>::from_str_truncating("ABC");
+        assert_eq!((s.size(), s.as_str()), (3, Ok("ABC")));
+        let s = TarFormatString::<3>::from_str_truncating("ABCD");
+        assert_eq!((s.size(), s.as_str()), (3, Ok("ABC")));
+
+        // multi-byte characters that don't fit are dropped entirely
+        let s = TarFormatString::<3>::from_str_truncating("Aä");
+        assert_eq!(s.as_str(), Ok("Aä"));
+        let s = TarFormatString::<3>::from_str_truncating("ABä");
+        assert_eq!(s.as_str(), Ok("AB"));
+        let s = TarFormatString::<3>::from_str_truncating("€");
+        assert_eq!(s.as_str(), Ok("€"));
+        let s = TarFormatString::<2>::from_str_truncating("€");
+        assert!(s.is_empty());
+    }
+
     #[test]
     fn test_str_until_first_space() {
         let s = TarFormatString::new([b'A', b'B', b' ', b'X', 0]);
@@ -274,11 +718,178 @@ mod tar_format_string_tests {
         assert_eq!(s.size(), 20);
         assert_eq!(s.as_str(), Ok("ABCDEFAAAAAAAAAAAAAZ"));
     }
+
+    #[test]
+    fn test_try_append() {
+        // A prefix and a name that would overflow 256 bytes.
+        let mut s = TarFormatString::<256>::try_from_str(&"a".repeat(200)).unwrap();
+        assert_eq!(s.try_append(&TarFormatString::new([b'/'])), Ok(()));
+        assert_eq!(
+            s.try_append(&TarFormatString::new([b'b'; 100])),
+            Err(CapacityError {
+                requested: 301,
+                available: 256
+            })
+        );
+        // Then the string is unchanged
+        assert_eq!(s.size(), 201);
+
+        assert_eq!(s.try_append(&TarFormatString::new([b'b'; 55])), Ok(()));
+        assert_eq!(s.size(), 256);
+        assert!(s.try_append(&TarFormatString::new([b'c'])).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Result to long for capacity 4")]
+    fn test_append_panics() {
+        let mut s = TarFormatString::new([b'A'; 4]);
+        s.append(&TarFormatString::new([b'B']));
+    }
+
+    #[test]
+    fn test_append_str() {
+        let mut s = TarFormatString::new([0; 8]);
+
+        // When adding an empty string
+        assert_eq!(s.append_str(""), Ok(()));
+        // Then the result is no change
+        assert!(s.is_empty());
+        assert_eq!(s.as_str(), Ok(""));
+
+        // When adding ABC
+        assert_eq!(s.append_str("ABC"), Ok(()));
+        // Then the string contains the additional 3 chars
+        assert_eq!(s.size(), 3);
+        assert_eq!(s.as_str(), Ok("ABC"));
+
+        // When adding too many chars
+        assert_eq!(
+            s.append_str("DEFGHI"),
+            Err(CapacityError {
+                requested: 9,
+                available: 8
+            })
+        );
+        // Then the string is unchanged
+        assert_eq!(s.as_str(), Ok("ABC"));
+
+        // When filling the remaining capacity
+        assert_eq!(s.append_str("DEFGH"), Ok(()));
+        // Then the string is full and not null terminated
+        assert_eq!(s.size(), 8);
+        assert_eq!(s.as_str(), Ok("ABCDEFGH"));
+        assert!(s.append_str("I").is_err());
+    }
 }
 
 #[cfg(test)]
 mod tar_format_number_tests {
-    use crate::{TarFormatDecimal, TarFormatNumber, TarFormatString};
+    use crate::{TarFormatDecimal, TarFormatNumber, TarFormatOctal, TarFormatString};
+
+    #[test]
+    fn test_octal_from_number() {
+        let octal = TarFormatOctal::<8>::from_number(0o644).unwrap();
+        assert_eq!(octal.as_inner().as_str(), Ok("0000644"));
+        assert_eq!(octal.as_number::<u64>(), Ok(0o644));
+
+        // 7 octal digits is the maximum => base-256 encoding
+        let octal = TarFormatOctal::<8>::from_number(0o10000000).unwrap();
+        assert_eq!(
+            unsafe { core::mem::transmute::<TarFormatOctal<8>, [u8; 8]>(octal) },
+            [0x80, 0, 0, 0, 0, 0x20, 0, 0]
+        );
+
+        // 56 bits is the maximum for 8 byte fields
+        assert!(TarFormatOctal::<8>::from_number(1 << 55).is_some());
+        assert!(TarFormatOctal::<8>::from_number(1 << 56).is_none());
+        assert!(TarFormatOctal::<12>::from_number(u64::MAX).is_some());
+    }
+
+    #[test]
+    fn test_number_debug() {
+        let number = TarFormatOctal::<8>::new(*b"0000644\0");
+        assert_eq!(format!("{number:?}"), "420 [0000644]");
+        let number = TarFormatOctal::<8>::new(*b"0000x4\n\0");
+        assert_eq!(
+            format!("{number:?}"),
+            "invalid digit found in string [0000x4\\n]"
+        );
+        // must not panic
+        let number = TarFormatOctal::<8>::new(*b"\xff\xfe12\n\0\0\0");
+        assert!(format!("{number:?}").ends_with(r"[\xff\xfe12\n]"));
+    }
+
+    #[test]
+    fn test_as_number_base256() {
+        const TEN_GIB: u64 = 10 << 30;
+        let octal = TarFormatOctal::<12>::from_number(TEN_GIB).unwrap();
+        assert!(octal.is_base256());
+        assert_eq!(octal.as_number::<u64>(), Ok(TEN_GIB));
+        assert_eq!(octal.as_number::<i64>(), Ok(TEN_GIB as i64));
+        assert!(octal.as_number::<u32>().is_err());
+
+        // negative
+        let octal = TarFormatOctal::<8>::new([0xff; 8]);
+        assert_eq!(octal.as_number::<i64>(), Ok(-1));
+        assert!(octal.as_number::<u64>().is_err());
+        let octal =
+            TarFormatOctal::<12>::new([0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        assert_eq!(octal.as_number::<i64>(), Ok(-(1 << 32)));
+
+        // the largest values
+        let mut bytes = [0xff; 12];
+        bytes[0] = 0xbf;
+        let octal = TarFormatOctal::<12>::new(bytes);
+        assert_eq!(octal.as_number::<u128>(), Ok((1 << 94) - 1));
+        assert!(octal.as_number::<u64>().is_err());
+
+        assert_eq!(
+            std::format!(
+                "{:?}",
+                TarFormatOctal::<8>::new([0x80, 0, 0, 0, 0, 0, 1, 0])
+            ),
+            r"256 [\x80]"
+        );
+    }
+
+    #[test]
+    fn test_as_number_with_fallback() {
+        // decimal value in an octal field
+        let number = TarFormatOctal::<12>::new(*b"00000001999\0");
+        assert!(number.as_number::<u64>().is_err());
+        assert_eq!(number.as_number_with_fallback::<u64>(10), Ok(1999));
+        // the declared radix takes precedence
+        let number = TarFormatOctal::<12>::new(*b"00000001001\0");
+        assert_eq!(number.as_number_with_fallback::<u64>(10), Ok(513));
+        // both fail
+        let number = TarFormatOctal::<12>::new(*b"0000000100a\0");
+        assert_eq!(
+            number.as_number_with_fallback::<u64>(10),
+            number.as_number::<u64>()
+        );
+        assert_eq!(number.as_number_with_fallback::<u64>(16), Ok(0x100a));
+        // base-256
+        let size = 10 * 1024 * 1024 * 1024;
+        let number = TarFormatOctal::<12>::from_number(size).unwrap();
+        assert!(number.is_base256());
+        assert_eq!(number.as_number_with_fallback::<u64>(10), Ok(size));
+    }
+
+    #[test]
+    fn test_as_number_with_line_breaks() {
+        let number = TarFormatOctal::<8>::new(*b"0000644\r");
+        assert_eq!(number.as_number::<u64>(), Ok(0o644));
+        let number = TarFormatOctal::<8>::new(*b"000644\r\n");
+        assert_eq!(number.as_number::<u64>(), Ok(0o644));
+        let number = TarFormatOctal::<12>::new(*b"\n0000001001\0");
+        assert_eq!(number.as_number::<u64>(), Ok(513));
+        let number = TarFormatOctal::<8>::new(*b"00\r0644\0");
+        assert!(number.as_number::<u64>().is_err());
+
+        // strings are preserved verbatim
+        let s = TarFormatString::<4>::new(*b"ab\r\n");
+        assert_eq!(s.as_str(), Ok("ab\r\n"));
+    }
 
     #[test]
     fn test_as_number_with_space_in_string() {
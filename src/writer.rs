@@ -0,0 +1,620 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for [`TarBuilder`], which creates Tar archives in the ustar or the
+//! GNU format. This is only available with the `alloc` feature.
+
+use crate::header::{Mode, ModeFlags, PosixHeader, TypeFlag, TypeFlagRaw};
+use crate::tar_format_types::{TarFormatOctal, TarFormatString};
+use crate::{TarArchiveRef, BLOCKSIZE, MIN_BLOCK_COUNT, NAME_LEN, PREFIX_LEN};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+
+/// Errors that may happen when appending entries to a [`TarBuilder`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The name doesn't fit into the `name` and `prefix` fields of the
+    /// header.
+    NameTooLong,
+    /// The name of an entry that is copied from an archive isn't valid
+    /// UTF-8.
+    InvalidName,
+    /// The user or group name doesn't fit into its header field.
+    OwnerNameTooLong,
+    /// A numeric value doesn't fit into its header field. Only
+    /// [`TarFormat::Gnu`] falls back to the base-256 encoding.
+    NumberTooLarge,
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl core::error::Error for BuildError {}
+
+/// Metadata of an entry appended to a [`TarBuilder`].
+///
+/// The default matches what GNU Tar emits for a file with permissions
+/// `0644` that is owned by `root`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EntryOptions<'a> {
+    /// UNIX file permissions.
+    pub mode: ModeFlags,
+    /// Numeric user ID of the owner.
+    pub uid: u64,
+    /// Numeric group ID of the owner.
+    pub gid: u64,
+    /// Modification time in seconds since the Unix epoch.
+    pub mtime: u64,
+    /// User name of the owner. At most 31 bytes.
+    pub uname: &'a str,
+    /// Group name of the owner. At most 31 bytes.
+    pub gname: &'a str,
+}
+
+impl Default for EntryOptions<'_> {
+    fn default() -> Self {
+        Self {
+            mode: ModeFlags::from_bits_truncate(0o644),
+            uid: 0,
+            gid: 0,
+            mtime: 0,
+            uname: "root",
+            gname: "root",
+        }
+    }
+}
+
+/// Format of the headers that a [`TarBuilder`] writes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TarFormat {
+    /// POSIX ustar. Long names are split into the `prefix` and `name`
+    /// fields at a `/`, which limits them to 256 bytes. Numbers that don't
+    /// fit into the octal digits of their field are rejected with
+    /// [`BuildError::NumberTooLarge`], as strict POSIX readers don't
+    /// support the base-256 encoding of GNU Tar.
+    #[default]
+    Ustar,
+    /// GNU Tar. Like GNU Tar, names that don't fit into the `name` field are
+    /// stored in a preceding `././@LongLink` record of type `L`, which has
+    /// no length limit. The `prefix` field is not used. Numbers that don't
+    /// fit into the octal digits of their field use the base-256 encoding.
+    Gnu,
+}
+
+/// Builder for Tar archives in the ustar or the GNU format. See
+/// [`TarFormat`].
+///
+/// Entries are appended one after another and [`TarBuilder::finish`] returns
+/// the bytes of the archive, which can be read by [`crate::TarArchive`] or
+/// [`crate::TarArchiveRef`].
+///
+/// This is only available with the `alloc` feature of this crate.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TarBuilder {
+    data: Vec<u8>,
+    format: TarFormat,
+}
+
+impl TarBuilder {
+    /// Creates a new builder for the ustar format without any entries.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_format(TarFormat::Ustar)
+    }
+
+    /// Creates a new builder for the given format without any entries.
+    #[must_use]
+    pub const fn with_format(format: TarFormat) -> Self {
+        Self {
+            data: Vec::new(),
+            format,
+        }
+    }
+
+    /// Returns the minimal valid archive without any entries. It only
+    /// consists of zero blocks, so that it satisfies [`MIN_BLOCK_COUNT`].
+    #[must_use]
+    pub fn empty() -> Box<[u8]> {
+        Self::new().finish()
+    }
+
+    /// Appends a regular file with the default [`EntryOptions`].
+    ///
+    /// In the ustar format, names longer than 100 bytes are split into the
+    /// `prefix` and `name` fields at a `/`, which limits the name to 256
+    /// bytes. The GNU format has no such limit.
+    pub fn append_file(&mut self, name: &str, data: &[u8]) -> Result<(), BuildError> {
+        self.append_file_with(name, &EntryOptions::default(), data)
+    }
+
+    /// Appends a regular file with the given metadata.
+    ///
+    /// In the ustar format, names longer than 100 bytes are split into the
+    /// `prefix` and `name` fields at a `/`, which limits the name to 256
+    /// bytes. The GNU format has no such limit.
+    pub fn append_file_with(
+        &mut self,
+        name: &str,
+        options: &EntryOptions,
+        data: &[u8],
+    ) -> Result<(), BuildError> {
+        self.append_entry(name, TypeFlag::REGTYPE.into(), options, data)
+    }
+
+    /// Appends a directory with the default [`EntryOptions`] but with
+    /// permissions `0755`. Like GNU Tar, a `/` is appended to the name, if
+    /// it doesn't end with one.
+    ///
+    /// Directories are not yielded by [`TarArchiveRef::entries`], but
+    /// extraction tools create them with their metadata.
+    pub fn append_dir(&mut self, name: &str) -> Result<(), BuildError> {
+        let options = EntryOptions {
+            mode: ModeFlags::from_bits_truncate(0o755),
+            ..Default::default()
+        };
+        self.append_dir_with(name, &options)
+    }
+
+    /// Appends a directory with the given metadata. See [`Self::append_dir`].
+    pub fn append_dir_with(
+        &mut self,
+        name: &str,
+        options: &EntryOptions,
+    ) -> Result<(), BuildError> {
+        if name.ends_with('/') {
+            self.append_entry(name, TypeFlag::DIRTYPE.into(), options, &[])
+        } else {
+            let name = String::from(name) + "/";
+            self.append_entry(&name, TypeFlag::DIRTYPE.into(), options, &[])
+        }
+    }
+
+    /// Appends a header of the given type for the data, followed by the
+    /// data.
+    fn append_entry(
+        &mut self,
+        name: &str,
+        typeflag: TypeFlagRaw,
+        options: &EntryOptions,
+        data: &[u8],
+    ) -> Result<(), BuildError> {
+        let size = data.len() as u64;
+        let hdr = match self.format {
+            TarFormat::Ustar => {
+                Self::create_header(split_name(name)?, typeflag, options, size, TarFormat::Ustar)?
+            }
+            TarFormat::Gnu => {
+                let hdr = Self::create_gnu_header(name, typeflag, options, size)?;
+                if name.len() > NAME_LEN {
+                    self.append_gnu_long_name(name, options)?;
+                }
+                hdr
+            }
+        };
+        self.data.extend_from_slice(hdr.as_bytes());
+        self.data.extend_from_slice(data);
+        self.pad_to_block_boundary();
+        Ok(())
+    }
+
+    /// Copies the regular files of `src` for which `filter` returns `true`
+    /// verbatim into the builder and returns the number of copied files.
+    ///
+    /// The headers are copied as they are together with the PAX extended
+    /// headers and GNU long names that belong to the files, so all metadata
+    /// is preserved (see [`crate::ArchiveEntry::raw_blocks_with_extensions`]). The
+    /// terminator of `src` is skipped, as [`Self::finish`] appends one.
+    pub fn append_archive(
+        &mut self,
+        src: &TarArchiveRef,
+        mut filter: impl FnMut(&PosixHeader) -> bool,
+    ) -> usize {
+        let mut count = 0;
+        for entry in src.entries() {
+            if filter(entry.posix_header()) {
+                self.data
+                    .extend_from_slice(entry.raw_blocks_with_extensions());
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Appends the terminating zero blocks and returns the archive. The
+    /// archive is at least [`MIN_BLOCK_COUNT`] blocks long.
+    #[must_use]
+    pub fn finish(mut self) -> Box<[u8]> {
+        let len = (self.data.len() + 2 * BLOCKSIZE).max(MIN_BLOCK_COUNT * BLOCKSIZE);
+        self.data.resize(len, 0);
+        self.data.into_boxed_slice()
+    }
+
+    /// Appends a `././@LongLink` record of type `L` whose payload is the
+    /// NULL-terminated name, as GNU Tar does.
+    fn append_gnu_long_name(
+        &mut self,
+        name: &str,
+        options: &EntryOptions,
+    ) -> Result<(), BuildError> {
+        let size = name.len() as u64 + 1;
+        let hdr =
+            Self::create_gnu_header("././@LongLink", TypeFlagRaw::from_byte(b'L'), options, size)?;
+        self.data.extend_from_slice(hdr.as_bytes());
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.push(0);
+        self.pad_to_block_boundary();
+        Ok(())
+    }
+
+    /// Creates a GNU header with a valid checksum. The name is truncated to
+    /// the `name` field.
+    fn create_gnu_header(
+        name: &str,
+        typeflag: TypeFlagRaw,
+        options: &EntryOptions,
+        size: u64,
+    ) -> Result<PosixHeader, BuildError> {
+        let mut bytes = [0; NAME_LEN];
+        let len = name.len().min(NAME_LEN);
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        let names = (
+            TarFormatString::new([0; PREFIX_LEN]),
+            TarFormatString::new(bytes),
+        );
+        let mut hdr = Self::create_header(names, typeflag, options, size, TarFormat::Gnu)?;
+        hdr.magic = TarFormatString::new(*b"ustar ");
+        hdr.version = TarFormatString::new(*b" \0");
+        hdr.recompute_checksum();
+        Ok(hdr)
+    }
+
+    /// Creates a ustar header with a valid checksum. The format decides
+    /// whether numbers may use the base-256 encoding.
+    fn create_header(
+        (prefix, name): (TarFormatString<PREFIX_LEN>, TarFormatString<NAME_LEN>),
+        typeflag: TypeFlagRaw,
+        options: &EntryOptions,
+        size: u64,
+        format: TarFormat,
+    ) -> Result<PosixHeader, BuildError> {
+        let owner_name = |name: &str| {
+            (name.len() < 32)
+                .then(|| TarFormatString::try_from_str(name).ok())
+                .flatten()
+                .ok_or(BuildError::OwnerNameTooLong)
+        };
+        let mut hdr = PosixHeader {
+            name,
+            mode: Mode::from(options.mode),
+            uid: octal(options.uid, format)?,
+            gid: octal(options.gid, format)?,
+            size: octal(size, format)?,
+            mtime: octal(options.mtime, format)?,
+            cksum: TarFormatOctal::new([0; 8]),
+            typeflag,
+            linkname: TarFormatString::new([0; NAME_LEN]),
+            magic: TarFormatString::new(*b"ustar\0"),
+            version: TarFormatString::new(*b"00"),
+            uname: owner_name(options.uname)?,
+            gname: owner_name(options.gname)?,
+            dev_major: octal(0, format)?,
+            dev_minor: octal(0, format)?,
+            prefix,
+            _pad: [0; 12],
+        };
+        hdr.recompute_checksum();
+        Ok(hdr)
+    }
+
+    /// Pads the data with zeroes to the next multiple of [`BLOCKSIZE`].
+    fn pad_to_block_boundary(&mut self) {
+        let len = self.data.len().next_multiple_of(BLOCKSIZE);
+        self.data.resize(len, 0);
+    }
+}
+
+/// Encodes a number for a numeric header field. Only the GNU format may use
+/// the base-256 encoding.
+fn octal<const N: usize>(value: u64, format: TarFormat) -> Result<TarFormatOctal<N>, BuildError> {
+    TarFormatOctal::from_number(value)
+        .filter(|number| format == TarFormat::Gnu || !number.is_base256())
+        .ok_or(BuildError::NumberTooLarge)
+}
+
+/// Splits a name into the `prefix` and the `name` field of a ustar header.
+/// The split happens at the first `/` that makes both parts fit.
+fn split_name(
+    name: &str,
+) -> Result<(TarFormatString<PREFIX_LEN>, TarFormatString<NAME_LEN>), BuildError> {
+    if let Ok(name) = TarFormatString::try_from_str(name) {
+        return Ok((TarFormatString::new([0; PREFIX_LEN]), name));
+    }
+
+    name.match_indices('/')
+        .map(|(index, _)| (&name[..index], &name[index + 1..]))
+        .filter(|(_, name)| !name.is_empty())
+        .find_map(|(prefix, name)| {
+            Some((
+                TarFormatString::try_from_str(prefix).ok()?,
+                TarFormatString::try_from_str(name).ok()?,
+            ))
+        })
+        .ok_or(BuildError::NameTooLong)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VersionKind;
+    use std::string::String;
+    use std::vec::Vec;
+
+    /// Returns the header at the given block index.
+    fn header_at(data: &[u8], block_index: usize) -> PosixHeader {
+        PosixHeader::from_block(&data[block_index * BLOCKSIZE..(block_index + 1) * BLOCKSIZE])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_builder_roundtrip() {
+        let long_name = "0123456789/".repeat(12) + "file.txt";
+        let options = EntryOptions {
+            mode: ModeFlags::from_bits_truncate(0o755),
+            uid: 1000,
+            gid: 100,
+            mtime: 1633330466,
+            uname: "phip1611",
+            gname: "users",
+        };
+
+        let mut builder = TarBuilder::new();
+        builder
+            .append_file("hello_world.txt", b"Hello World\n")
+            .unwrap();
+        builder
+            .append_file_with(&long_name, &options, &[b'a'; 513])
+            .unwrap();
+        let data = builder.finish();
+        assert_eq!(data.len() % BLOCKSIZE, 0);
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].filename().as_str(), Ok("hello_world.txt"));
+        assert_eq!(entries[0].data(), b"Hello World\n");
+        assert_eq!(entries[1].filename().as_str(), Ok(long_name.as_str()));
+        assert_eq!(entries[1].data(), &[b'a'; 513]);
+
+        let hdr = header_at(&data, 0);
+        assert_eq!(hdr.mode.to_flags().unwrap().bits(), 0o644);
+        assert_eq!(hdr.uname.as_str(), Ok("root"));
+        assert_eq!(
+            hdr.compute_checksum(),
+            hdr.cksum.as_number::<u64>().unwrap()
+        );
+
+        let hdr = header_at(&data, 2);
+        assert_eq!(hdr.mode.to_flags().unwrap().bits(), 0o755);
+        assert_eq!(hdr.uid.as_number::<u64>(), Ok(1000));
+        assert_eq!(hdr.gid.as_number::<u64>(), Ok(100));
+        assert_eq!(hdr.mtime.as_number::<u64>(), Ok(1633330466));
+        assert_eq!(hdr.uname.as_str(), Ok("phip1611"));
+        assert_eq!(hdr.gname.as_str(), Ok("users"));
+        assert_eq!(
+            hdr.compute_checksum(),
+            hdr.cksum.as_number::<u64>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_builder_base256_numbers() {
+        let options = EntryOptions {
+            uid: 1 << 40,
+            ..Default::default()
+        };
+        let mut builder = TarBuilder::with_format(TarFormat::Gnu);
+        builder.append_file_with("file", &options, &[]).unwrap();
+        let data = builder.finish();
+
+        // uid field
+        assert_eq!(&data[108..116], &[0x80, 0, 1, 0, 0, 0, 0, 0]);
+
+        // strict POSIX readers don't support the base-256 encoding
+        let mut builder = TarBuilder::new();
+        assert_eq!(
+            builder.append_file_with("file", &options, &[]),
+            Err(BuildError::NumberTooLarge)
+        );
+        assert_eq!(builder.finish(), TarBuilder::empty());
+    }
+
+    #[test]
+    fn test_builder_empty() {
+        let data = TarBuilder::empty();
+        assert_eq!(data.len(), MIN_BLOCK_COUNT * BLOCKSIZE);
+        assert_eq!(data, TarBuilder::new().finish());
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.entries().count(), 0);
+    }
+
+    #[test]
+    fn test_builder_dir() {
+        let mut builder = TarBuilder::new();
+        builder.append_dir("dir").unwrap();
+        builder.append_dir("dir/sub/").unwrap();
+        builder.append_file("dir/sub/file.txt", b"file").unwrap();
+        let data = builder.finish();
+
+        let hdr = header_at(&data, 0);
+        assert_eq!(hdr.name.as_str(), Ok("dir/"));
+        assert_eq!(hdr.typeflag.try_to_type_flag(), Ok(TypeFlag::DIRTYPE));
+        assert_eq!(hdr.mode.to_flags().unwrap().bits(), 0o755);
+        assert_eq!(hdr.size.as_number::<u64>(), Ok(0));
+        assert!(hdr.checksum_is_valid());
+        assert_eq!(header_at(&data, 1).name.as_str(), Ok("dir/sub/"));
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        assert_eq!(archive.inspect().get(TypeFlag::DIRTYPE), 2);
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].filename().as_str(), Ok("dir/sub/file.txt"));
+        assert_eq!(entries[0].data(), b"file");
+    }
+
+    #[test]
+    fn test_builder_append_archive() {
+        let src = TarArchiveRef::new(include_bytes!("../tests/gnu_tar_default.tar")).unwrap();
+        let mut builder = TarBuilder::new();
+        builder.append_file("first.txt", b"first").unwrap();
+        let count = builder.append_archive(&src, |hdr| hdr.name.as_str().unwrap().contains("513b"));
+        assert_eq!(count, 2);
+        let data = builder.finish();
+
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let names = archive
+            .entries()
+            .map(|entry| entry.filename())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names
+                .iter()
+                .map(|name| name.as_str().unwrap())
+                .collect::<Vec<_>>(),
+            ["first.txt", "bye_world_513b.txt", "hello_world_513b.txt"]
+        );
+        // copied verbatim
+        for (copied, original) in archive.entries().skip(1).zip(src.entries()) {
+            assert_eq!(copied.posix_header(), original.posix_header());
+            assert_eq!(copied.data(), original.data());
+        }
+
+        let mut builder = TarBuilder::new();
+        assert_eq!(builder.append_archive(&src, |_| false), 0);
+        assert_eq!(builder.finish(), TarBuilder::empty());
+    }
+
+    #[test]
+    fn test_builder_append_archive_long_name() {
+        let long_name = "a/".repeat(75) + "xy";
+        let mut builder = TarBuilder::with_format(TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        builder.append_file("short.txt", b"short").unwrap();
+        let data = builder.finish();
+        let src = TarArchiveRef::new(&data).unwrap();
+
+        let mut builder = TarBuilder::new();
+        assert_eq!(builder.append_archive(&src, |_| true), 2);
+        let data = builder.finish();
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].filename_string(), long_name);
+        assert_eq!(entries[0].data(), b"long");
+        assert_eq!(entries[1].filename_string(), "short.txt");
+    }
+
+    #[test]
+    fn test_builder_gnu_long_name() {
+        let long_name = "0123456789/".repeat(27) + "file.txt";
+        assert_eq!(long_name.len(), 305);
+        let mut builder = TarBuilder::with_format(TarFormat::Gnu);
+        builder.append_file(&long_name, b"long").unwrap();
+        builder.append_file("short.txt", b"short").unwrap();
+        let options = EntryOptions {
+            uname: "a-very-long-user-name-with-32-ch",
+            ..Default::default()
+        };
+        // nothing is written on errors
+        assert_eq!(
+            builder.append_file_with(&long_name, &options, &[]),
+            Err(BuildError::OwnerNameTooLong)
+        );
+        let data = builder.finish();
+        assert_eq!(data.len(), 8 * BLOCKSIZE);
+
+        let hdr = header_at(&data, 0);
+        assert_eq!(hdr.name.as_str(), Ok("././@LongLink"));
+        assert_eq!(hdr.typeflag.as_byte(), b'L');
+        assert_eq!(hdr.size.as_number::<usize>(), Ok(long_name.len() + 1));
+        assert_eq!(&data[BLOCKSIZE..][..long_name.len()], long_name.as_bytes());
+        assert_eq!(data[BLOCKSIZE + long_name.len()], 0);
+
+        let hdr = header_at(&data, 2);
+        assert_eq!(hdr.version_kind(), VersionKind::Gnu);
+        assert_eq!(hdr.name.as_str(), Ok(&long_name[..NAME_LEN]));
+        assert!(hdr.prefix.is_empty());
+        assert!(hdr.checksum_is_valid());
+
+        let hdr = header_at(&data, 4);
+        assert_eq!(hdr.name.as_str(), Ok("short.txt"));
+        assert_eq!(hdr.version_kind(), VersionKind::Gnu);
+
+        // The header chain is intact.
+        let stats = TarArchiveRef::new(&data).unwrap().inspect();
+        assert_eq!(stats.get(TypeFlag::REGTYPE), 2);
+        assert_eq!(stats.get(TypeFlag::GNU_LONGNAME), 1);
+        assert_eq!(stats.invalid_count(), 0);
+
+        // The long name is read back.
+        let archive = TarArchiveRef::new(&data).unwrap();
+        let entries = archive.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].long_name(), Some(long_name.as_bytes()));
+        assert_eq!(entries[0].data(), b"long");
+        assert_eq!(entries[1].long_name(), None);
+        assert_eq!(entries[1].filename().as_str(), Ok("short.txt"));
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let mut builder = TarBuilder::new();
+        let name = String::from("a").repeat(101);
+        assert_eq!(
+            builder.append_file(&name, &[]),
+            Err(BuildError::NameTooLong)
+        );
+        let options = EntryOptions {
+            uname: "a-very-long-user-name-with-32-ch",
+            ..Default::default()
+        };
+        assert_eq!(
+            builder.append_file_with("file", &options, &[]),
+            Err(BuildError::OwnerNameTooLong)
+        );
+        let options = EntryOptions {
+            gid: 1 << 56,
+            ..Default::default()
+        };
+        assert_eq!(
+            builder.append_file_with("file", &options, &[]),
+            Err(BuildError::NumberTooLarge)
+        );
+    }
+}